@@ -1 +1,45 @@
 pub const ANCHOR_DISCRIMINATOR: usize = 8;
+
+/// Longest base URI the admin can set; leaves room for the pet number and
+/// level/mood query within Metaplex's 200 byte URI limit.
+pub const MAX_BASE_URI_LENGTH: usize = 150;
+
+/// Seconds a player may take to attack before the opponent can claim a forfeit.
+pub const DEFAULT_TURN_TIMEOUT: i64 = 24 * 60 * 60;
+
+/// Seconds after which anyone may cancel an unaccepted challenge and refund the challenger.
+pub const DEFAULT_CHALLENGE_EXPIRY: i64 = 7 * 24 * 60 * 60;
+
+/// Number of unfinished duels a single pet may take part in at once.
+pub const MAX_ACTIVE_DUELS: u8 = 3;
+
+/// Own turns a pet must wait before using its special attack again.
+pub const SPECIAL_COOLDOWN_TURNS: u8 = 3;
+
+/// Own turns a pet must wait before healing again.
+pub const HEAL_COOLDOWN_TURNS: u8 = 2;
+
+/// Energy a pet spends to enter a duel; it must have at least this much to fight.
+pub const DUEL_ENERGY_COST: u8 = 20;
+
+/// Hunger and hygiene a pet loses by taking part in a duel.
+pub const DUEL_HUNGER_COST: u8 = 10;
+pub const DUEL_HYGIENE_COST: u8 = 10;
+
+/// Experience awarded to each pet when a duel ends.
+pub const XP_PER_WIN: u64 = 100;
+pub const XP_PER_LOSS: u64 = 25;
+
+/// Experience needed per level; pets start at level 1.
+pub const XP_PER_LEVEL: u64 = 200;
+pub const MAX_PET_LEVEL: u8 = 50;
+
+/// SPL tokens the admin can accept for duel bets.
+pub const MAX_BET_MINTS: usize = 4;
+
+/// Duel fees are charged in basis points of the pot, capped at 10%.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_FEE_BPS: u16 = 1_000;
+
+/// Seconds before a fee change proposed with `update_fees` takes effect.
+pub const FEE_CHANGE_DELAY: i64 = 2 * 24 * 60 * 60;
@@ -6,8 +6,10 @@ pub enum ErrorCode {
     ConfigAlreadyInitialized,
     #[msg("Invalid admin access.")]
     InvalidAdminAccess,
-    #[msg("Fees percentage must be between 0 and 100.")]
+    #[msg("Fee exceeds the maximum fee rate.")]
     InvalidFeesPercentage,
+    #[msg("Game is paused.")]
+    GamePaused,
 
     #[msg("Incorrect collection mint.")]
     IncorrectCollectionMint,
@@ -17,8 +19,32 @@ pub enum ErrorCode {
     NftNotDeposited,
     #[msg("Player does not own the NFT.")]
     PlayerDoesNotOwnNft,
+    #[msg("Pet stats belong to a different NFT mint.")]
+    PetMintMismatch,
+    #[msg("Token program does not match the collection's.")]
+    InvalidTokenProgram,
+    #[msg("An account required by the pet standard is missing.")]
+    MissingPetAccount,
     #[msg("Game ATA creation failed.")]
     GameAtaCreationFailed,
+    #[msg("Base URI is too long.")]
+    InvalidBaseUri,
+    #[msg("Mint settings are invalid.")]
+    InvalidMintSettings,
+    #[msg("Minting is not open.")]
+    MintNotOpen,
+    #[msg("Maximum pet supply reached.")]
+    MaxSupplyReached,
+    #[msg("Wallet has minted the maximum number of pets.")]
+    WalletMintLimitReached,
+    #[msg("Presale settings are invalid.")]
+    InvalidPresaleSettings,
+    #[msg("Presale is not active.")]
+    PresaleNotActive,
+    #[msg("Wallet is not on the presale allowlist.")]
+    NotOnAllowlist,
+    #[msg("Wallet has used its presale allowance.")]
+    PresaleMintLimitReached,
 
     #[msg("Invalid pet interaction.")]
     InvalidPetInteraction,
@@ -39,11 +65,15 @@ pub enum ErrorCode {
     InvalidBetAmount,
     #[msg("Not enough funds to place bet.")]
     NotEnoughFundsForBet,
+    #[msg("Pet is already in the maximum number of active duels.")]
+    TooManyActiveDuels,
 
     #[msg("Not challenger's turn.")]
     NotChallengerTurn,
     #[msg("Not defender's turn.")]
     NotDefenderTurn,
+    #[msg("Duel action is on cooldown.")]
+    ActionOnCooldown,
     #[msg("Duel is not challenged yet.")]
     DuelNotChallenged,
     #[msg("Duel is finished.")]
@@ -54,6 +84,30 @@ pub enum ErrorCode {
     NoWinner,
     #[msg("Unknown winner.")]
     UnknownWinner,
+    #[msg("Turn timeout has not elapsed yet.")]
+    TurnTimeoutNotElapsed,
+    #[msg("Turn timeout must be greater than zero.")]
+    InvalidTurnTimeout,
+    #[msg("Challenge has not expired yet.")]
+    ChallengeNotExpired,
+    #[msg("Challenge expiry must be greater than zero.")]
+    InvalidChallengeExpiry,
+    #[msg("Pet is in an unfinished duel or has unclaimed winnings.")]
+    PetInActiveDuel,
+    #[msg("Amount exceeds the fees available to withdraw.")]
+    InsufficientFees,
+    #[msg("Bet mint is not accepted for new duels.")]
+    BetMintNotAllowed,
+    #[msg("Bet mint does not match the duel.")]
+    BetMintMismatch,
+    #[msg("Too many bet mints.")]
+    TooManyBetMints,
+    #[msg("Bet mints with a transfer fee are not supported.")]
+    TransferFeeMintNotSupported,
+    #[msg("An account required for a token bet is missing.")]
+    MissingBetAccount,
+    #[msg("Vault token account must be the vault's associated token account.")]
+    InvalidVaultTokenAccount,
 
     #[msg("Unauthorized action.")]
     UnauthorizedAction,
@@ -89,5 +143,14 @@ pub enum ErrorCode {
     #[msg("Signature verification failed")]
     SignatureNotVerified,
     #[msg("Signature data does not match expected data")]
-    SigDataNoMatch
+    SigDataNoMatch,
+    #[msg("Signature was not produced by the expected signer")]
+    InvalidSigner,
+    #[msg("No Ed25519 instruction carries the expected signature")]
+    SignatureInstructionMissing,
+
+    #[msg("Revealed value does not match the commitment")]
+    InvalidReveal,
+    #[msg("Randomness authority is not configured")]
+    RandomnessAuthorityNotSet,
 }
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::get_associated_token_address_with_program_id,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::error::ErrorCode;
+
+/// Accounts that move an SPL token bet between a player and the vault's
+/// associated token account for the bet mint.
+pub struct BetTokenAccounts<'a, 'info> {
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub player_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    pub vault_token_account: &'a InterfaceAccount<'info, TokenAccount>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'a, 'info> BetTokenAccounts<'a, 'info> {
+    /// Collects the optional bet accounts of an instruction, checking that they are
+    /// for `bet_mint`, that the player side belongs to `player` and that the vault
+    /// side is the vault's associated token account.
+    #[allow(clippy::too_many_arguments)]
+    pub fn load(
+        bet_mint: Pubkey,
+        game_vault: &Pubkey,
+        player: &Pubkey,
+        mint: &'a Option<InterfaceAccount<'info, Mint>>,
+        player_token_account: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+        vault_token_account: &'a Option<InterfaceAccount<'info, TokenAccount>>,
+        token_program: &'a Option<Interface<'info, TokenInterface>>,
+    ) -> Result<Self> {
+        let accounts = Self {
+            mint: mint.as_ref().ok_or(ErrorCode::MissingBetAccount)?,
+            player_token_account: player_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingBetAccount)?,
+            vault_token_account: vault_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingBetAccount)?,
+            token_program: token_program.as_ref().ok_or(ErrorCode::MissingBetAccount)?,
+        };
+
+        require_keys_eq!(accounts.mint.key(), bet_mint, ErrorCode::BetMintMismatch);
+        require_keys_eq!(
+            accounts.player_token_account.owner,
+            *player,
+            ErrorCode::UnauthorizedAction
+        );
+        require_keys_eq!(
+            accounts.vault_token_account.key(),
+            get_associated_token_address_with_program_id(
+                game_vault,
+                &bet_mint,
+                &accounts.token_program.key()
+            ),
+            ErrorCode::InvalidVaultTokenAccount
+        );
+
+        Ok(accounts)
+    }
+
+    /// Moves `amount` from the player, who must sign, into the vault.
+    pub fn deposit(&self, player: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.player_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.vault_token_account.to_account_info(),
+            authority: player.clone(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+
+    /// Moves `amount` from the vault to the player, signing as the vault PDA.
+    pub fn pay_out(
+        &self,
+        game_vault: &AccountInfo<'info>,
+        vault_bump: u8,
+        amount: u64,
+    ) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.vault_token_account.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.player_token_account.to_account_info(),
+            authority: game_vault.clone(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", &[vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+}
@@ -0,0 +1,13 @@
+use anchor_lang::solana_program::hash::hashv;
+
+/// Walks `proof` from `leaf` up to the root, hashing each pair in sorted order.
+pub fn verify_merkle_proof(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, sibling| {
+        if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        }
+    });
+    computed == *root
+}
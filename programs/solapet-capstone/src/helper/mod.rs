@@ -2,4 +2,16 @@ pub mod verify_sig;
 pub use verify_sig::*;
 
 pub mod gen_number;
-pub use gen_number::*;
\ No newline at end of file
+pub use gen_number::*;
+
+pub mod merkle;
+pub use merkle::*;
+
+pub mod required_account;
+pub use required_account::*;
+
+pub mod payout;
+pub use payout::*;
+
+pub mod bet_tokens;
+pub use bet_tokens::*;
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, BPS_DENOMINATOR};
+
+/// How a finished duel's pot is split between the winner and the house.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Payout {
+    /// Both duelists' stakes.
+    pub pot: u64,
+    /// House cut, `fee_bps` basis points of the pot rounded down.
+    pub fee: u64,
+    /// What the winner receives, `pot - fee`.
+    pub winner: u64,
+}
+
+pub fn duel_payout(bet_amount: u64, fee_bps: u16) -> Result<Payout> {
+    require!(
+        fee_bps as u64 <= BPS_DENOMINATOR,
+        ErrorCode::InvalidFeesPercentage
+    );
+
+    let pot = bet_amount.checked_mul(2).ok_or(ErrorCode::Overflow)?;
+    // `pot * fee_bps` can exceed u64 for large token bets even though the fee fits.
+    let fee = (pot as u128)
+        .checked_mul(fee_bps as u128)
+        .ok_or(ErrorCode::Overflow)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(ErrorCode::Overflow)?;
+    let fee = u64::try_from(fee).map_err(|_| ErrorCode::Overflow)?;
+    let winner = pot.checked_sub(fee).ok_or(ErrorCode::Overflow)?;
+
+    Ok(Payout { pot, fee, winner })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn winner_takes_both_stakes_minus_fee() {
+        let payout = duel_payout(1_000, 100).unwrap();
+        assert_eq!(
+            payout,
+            Payout {
+                pot: 2_000,
+                fee: 20,
+                winner: 1_980
+            }
+        );
+    }
+
+    #[test]
+    fn no_fee_pays_out_the_whole_pot() {
+        let payout = duel_payout(500, 0).unwrap();
+        assert_eq!(payout.winner, 1_000);
+        assert_eq!(payout.fee, 0);
+    }
+
+    #[test]
+    fn fee_rounds_down() {
+        // 3% of 66 is 1.98
+        let payout = duel_payout(33, 300).unwrap();
+        assert_eq!(payout.fee, 1);
+        assert_eq!(payout.winner, 65);
+    }
+
+    #[test]
+    fn pot_is_always_fully_accounted_for() {
+        for (bet, fee_bps) in [
+            (1, 5_000),
+            (7, 1_337),
+            (1_000_000_007, 9_999),
+            (u64::MAX / 20_000, 10_000),
+            (u64::MAX / 2, 10_000),
+            (u64::MAX / 2, 9_999),
+        ] {
+            let payout = duel_payout(bet, fee_bps).unwrap();
+            assert_eq!(payout.fee + payout.winner, payout.pot);
+            assert_eq!(payout.pot, bet * 2);
+        }
+    }
+
+    #[test]
+    fn zero_bet_pays_nothing() {
+        let payout = duel_payout(0, 1_000).unwrap();
+        assert_eq!(payout.pot, 0);
+        assert_eq!(payout.winner, 0);
+    }
+
+    #[test]
+    fn overflowing_pot_is_an_error() {
+        assert_eq!(
+            duel_payout(u64::MAX / 2 + 1, 100).unwrap_err(),
+            ErrorCode::Overflow.into()
+        );
+    }
+
+    #[test]
+    fn fee_on_a_large_pot_does_not_overflow() {
+        // `pot * fee_bps` is far above u64::MAX here.
+        let payout = duel_payout(u64::MAX / 4, 1_000).unwrap();
+        assert_eq!(
+            payout,
+            Payout {
+                pot: 9_223_372_036_854_775_806,
+                fee: 922_337_203_685_477_580,
+                winner: 8_301_034_833_169_298_226
+            }
+        );
+    }
+
+    #[test]
+    fn fees_above_one_hundred_percent_are_rejected() {
+        assert_eq!(
+            duel_payout(100, 10_001).unwrap_err(),
+            ErrorCode::InvalidFeesPercentage.into()
+        );
+    }
+
+    #[test]
+    fn one_basis_point_is_a_hundredth_of_a_percent() {
+        let payout = duel_payout(5_000, 1).unwrap();
+        assert_eq!(payout.fee, 1);
+        assert_eq!(payout.winner, 9_999);
+    }
+}
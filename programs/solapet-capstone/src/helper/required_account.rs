@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// Unwraps an optional account that the configured pet standard needs.
+pub fn required_account<T>(account: &Option<T>) -> Result<&T> {
+    account
+        .as_ref()
+        .ok_or_else(|| error!(ErrorCode::MissingPetAccount))
+}
@@ -2,30 +2,59 @@ use anchor_instruction_sysvar::Ed25519InstructionSignatures;
 
 use anchor_lang::{
     prelude::*,
-    solana_program::{ed25519_program, sysvar::instructions::load_instruction_at_checked},
+    solana_program::{
+        ed25519_program,
+        sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+    },
 };
 
 use crate::error::ErrorCode;
 
-pub fn verify_ed25519_signature(instruction_account: &AccountInfo,sig: &[u8]) -> Result<()> {
-    let ix = load_instruction_at_checked(0, &instruction_account)?;
-
-    msg!("{}", ix.program_id);
-
-    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::Ed25519PrigramIdMissMatch);
-
-    require_eq!(ix.accounts.len(), 0, ErrorCode::InstructionAccountLengthNotZero);
-
-    let signatures = Ed25519InstructionSignatures::unpack(&ix.data)?.0;
-
-    require_eq!(signatures.len(), 1, ErrorCode::InvalidSigLength);
-    let signature = &signatures[0];
-
-    require!(signature.is_verifiable, ErrorCode::SignatureNotVerified);
-
-    require!(signature.signature.unwrap().eq(sig), ErrorCode::InvalidSig);
-
-    // require!(signature.message.as_ref().unwrap().eq(account_to_check.to_slice()), ErrorCode::InvalidSig);
-
-    Ok(())
+/// Looks through the instructions preceding the current one for an Ed25519 program
+/// instruction carrying `sig` from `signer` over exactly `message`.
+pub fn verify_ed25519_signature(
+    instruction_account: &AccountInfo,
+    sig: &[u8],
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instruction_account)?;
+
+    for index in 0..current_index {
+        let ix = load_instruction_at_checked(index as usize, instruction_account)?;
+
+        if ix.program_id != ed25519_program::ID {
+            continue;
+        }
+
+        require_eq!(
+            ix.accounts.len(),
+            0,
+            ErrorCode::InstructionAccountLengthNotZero
+        );
+
+        let signatures = Ed25519InstructionSignatures::unpack(&ix.data)?.0;
+
+        for signature in signatures.iter() {
+            if !signature.signature.is_some_and(|s| s.eq(sig)) {
+                continue;
+            }
+
+            require!(signature.is_verifiable, ErrorCode::SignatureNotVerified);
+
+            require!(
+                signature.public_key.is_some_and(|key| key.eq(signer)),
+                ErrorCode::InvalidSigner
+            );
+
+            require!(
+                signature.message.as_ref().is_some_and(|m| m.eq(message)),
+                ErrorCode::SigDataNoMatch
+            );
+
+            return Ok(());
+        }
+    }
+
+    err!(ErrorCode::SignatureInstructionMissing)
 }
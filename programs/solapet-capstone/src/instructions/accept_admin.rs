@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::GameConfig;
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(mut)]
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> AcceptAdmin<'info> {
+    pub fn accept_admin(&mut self) -> Result<()> {
+        self.game_config.accept_admin(self.new_admin.key())?;
+        Ok(())
+    }
+}
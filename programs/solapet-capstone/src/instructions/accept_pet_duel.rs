@@ -2,55 +2,99 @@ use anchor_lang::{
     prelude::*,
     system_program::{transfer, Transfer},
 };
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
-use crate::{error::ErrorCode, GameConfig, PetDuel, PetStats};
+use crate::{error::ErrorCode, BetTokenAccounts, CombatStats, GameConfig, PetDuel, PetStats};
 
 #[derive(Accounts)]
 pub struct AcceptPetDuel<'info> {
     #[account(mut)]
     pub defender: Signer<'info>,
 
-    /// CHECK: ?
-    pub challenger: AccountInfo<'info>,
-
     #[account(
+        mut,
         seeds = [b"game_config"],
         bump = game_config.bump,
+        constraint = !game_config.paused @ ErrorCode::GamePaused,
         has_one = game_vault
     )]
     pub game_config: Account<'info, GameConfig>,
 
     #[account(
+        mut,
         seeds = [b"vault"],
         bump
     )]
     pub game_vault: SystemAccount<'info>,
 
     #[account(
-        seeds = [b"stats", defender.key().as_ref()],
-        bump = pet_stats.bump
+        mut,
+        seeds = [b"stats", pet_stats.nft_mint.as_ref()],
+        bump = pet_stats.bump,
+        constraint = pet_stats.owner == defender.key() @ ErrorCode::PlayerDoesNotOwnNft
     )]
     pub pet_stats: Account<'info, PetStats>,
 
     #[account(
         mut,
-        seeds = [b"pet_duel", challenger.key().as_ref()],
+        seeds = [
+            b"pet_duel",
+            pet_duel_account.challenger_pet.as_ref(),
+            pet_duel_account.nonce.to_le_bytes().as_ref()
+        ],
         bump = pet_duel_account.bump
     )]
     pub pet_duel_account: Account<'info, PetDuel>,
 
+    /// SPL token of the bet; omit for SOL bets
+    pub bet_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Defender's token account the bet is taken from
+    #[account(mut)]
+    pub defender_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's associated token account for `bet_mint`
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> AcceptPetDuel<'info> {
-    pub fn accept_duel(&mut self) -> Result<()> {
-        let updated_pet_energy = self.pet_stats.update_pet_energy()?;
-        require!(updated_pet_energy >= 20, ErrorCode::InsufficientPetEnergy);
-        self.pet_duel_account.accept_duel(self.defender.key())?;
+    pub fn accept_duel(&mut self, commitment: [u8; 32]) -> Result<()> {
+        self.pet_stats.update_pet_stats()?;
+        let defender_combat = CombatStats::from_pet_stats(&self.pet_stats);
+        self.pet_stats.spend_duel_energy()?;
+        self.pet_stats.join_duel()?;
+        self.pet_duel_account.accept_duel(
+            self.defender.key(),
+            self.pet_stats.nft_mint,
+            commitment,
+            defender_combat,
+        )?;
         Ok(())
     }
 
     pub fn deposite(&mut self) -> Result<()> {
+        let bet_amount = self.pet_duel_account.bet_amount;
+        let bet_mint = self.pet_duel_account.bet_mint;
+        self.game_config.escrow_bet(bet_mint, bet_amount)?;
+
+        if let Some(bet_mint) = bet_mint {
+            return BetTokenAccounts::load(
+                bet_mint,
+                &self.game_vault.key(),
+                &self.defender.key(),
+                &self.bet_mint,
+                &self.defender_token_account,
+                &self.vault_token_account,
+                &self.token_program,
+            )?
+            .deposit(&self.defender.to_account_info(), bet_amount);
+        }
+
         let cpi_program = self.system_program.to_account_info();
 
         let cpi_accounts = Transfer {
@@ -60,7 +104,7 @@ impl<'info> AcceptPetDuel<'info> {
 
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
 
-        transfer(cpi_ctx, self.pet_duel_account.bet_amount)?;
+        transfer(cpi_ctx, bet_amount)?;
 
         Ok(())
     }
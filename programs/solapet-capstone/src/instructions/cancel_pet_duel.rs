@@ -0,0 +1,118 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{BetTokenAccounts, GameConfig, PetDuel, PetStats};
+
+#[derive(Accounts)]
+pub struct CancelPetDuel<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        address = pet_duel_account.challenger
+    )]
+    pub challanger: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = game_vault
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = game_config.vault_bump
+    )]
+    pub game_vault: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pet_duel",
+            pet_duel_account.challenger_pet.as_ref(),
+            pet_duel_account.nonce.to_le_bytes().as_ref()
+        ],
+        bump = pet_duel_account.bump,
+        close = challanger
+    )]
+    pub pet_duel_account: Account<'info, PetDuel>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.challenger_pet.as_ref()],
+        bump = pet_stats.bump
+    )]
+    pub pet_stats: Account<'info, PetStats>,
+
+    /// SPL token of the bet; omit for SOL bets
+    pub bet_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Challenger's token account the bet is refunded to
+    #[account(mut)]
+    pub challanger_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's associated token account for `bet_mint`
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelPetDuel<'info> {
+    pub fn cancel(&mut self) -> Result<()> {
+        self.pet_duel_account
+            .cancel_duel(self.caller.key(), self.game_config.challenge_expiry)?;
+        self.pet_stats.update_pet_stats()?;
+        self.pet_stats
+            .refund_duel_energy(&self.pet_duel_account.challenger_cost)?;
+        self.pet_stats.end_duel()?;
+        Ok(())
+    }
+
+    pub fn refund(&mut self) -> Result<()> {
+        let bet_amount = self.pet_duel_account.bet_amount;
+        let bet_mint = self.pet_duel_account.bet_mint;
+        self.game_config.release_escrow(bet_mint, bet_amount)?;
+
+        if let Some(bet_mint) = bet_mint {
+            return BetTokenAccounts::load(
+                bet_mint,
+                &self.game_vault.key(),
+                &self.pet_duel_account.challenger,
+                &self.bet_mint,
+                &self.challanger_token_account,
+                &self.vault_token_account,
+                &self.token_program,
+            )?
+            .pay_out(
+                &self.game_vault.to_account_info(),
+                self.game_config.vault_bump,
+                bet_amount,
+            );
+        }
+
+        let cpi_program = self.system_program.to_account_info();
+
+        let cpi_accounts = Transfer {
+            from: self.game_vault.to_account_info(),
+            to: self.challanger.to_account_info(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", &[self.game_config.vault_bump]]];
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
+
+        transfer(cpi_context, bet_amount)?;
+
+        Ok(())
+    }
+}
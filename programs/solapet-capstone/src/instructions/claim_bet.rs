@@ -2,18 +2,17 @@ use anchor_lang::{
     prelude::*,
     system_program::{transfer, Transfer},
 };
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
-use crate::{error::ErrorCode, GameConfig, PetDuel};
+use crate::{duel_payout, BetTokenAccounts, GameConfig, PetDuel, PetStats};
 
 #[derive(Accounts)]
 pub struct ClaimBetAmount<'info> {
     #[account(mut)]
     pub winner: Signer<'info>,
 
-    /// CHECK: ?
-    pub challanger: AccountInfo<'info>,
-
     #[account(
+        mut,
         seeds = [b"game_config"],
         bump = game_config.bump
     )]
@@ -21,12 +20,23 @@ pub struct ClaimBetAmount<'info> {
 
     #[account(
         mut,
-        seeds = [b"pet_duel", challanger.key().as_ref()],
+        seeds = [
+            b"pet_duel",
+            pet_duel_account.challenger_pet.as_ref(),
+            pet_duel_account.nonce.to_le_bytes().as_ref()
+        ],
         bump = pet_duel_account.bump,
         close = winner
     )]
     pub pet_duel_account: Account<'info, PetDuel>,
 
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.winner_pet().as_ref()],
+        bump = winner_pet_stats.bump
+    )]
+    pub winner_pet_stats: Account<'info, PetStats>,
+
     #[account(
         mut,
         seeds = [b"vault"],
@@ -34,11 +44,55 @@ pub struct ClaimBetAmount<'info> {
     )]
     pub game_vault: SystemAccount<'info>,
 
+    /// SPL token of the bet; omit for SOL bets
+    pub bet_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Winner's token account the pot is paid to
+    #[account(mut)]
+    pub winner_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's associated token account for `bet_mint`
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> ClaimBetAmount<'info> {
+    /// Frees the duel slot the winning pet held until its pot was claimed.
+    pub fn release_winner_pet(&mut self) -> Result<()> {
+        self.winner_pet_stats.end_duel()
+    }
+
     pub fn claim(&mut self) -> Result<()> {
+        // Both duelists staked `bet_amount`; the winner takes the pot minus fees.
+        let payout = duel_payout(
+            self.pet_duel_account.bet_amount,
+            self.pet_duel_account.fee_bps,
+        )?;
+        let bet_mint = self.pet_duel_account.bet_mint;
+        self.game_config
+            .settle_pot(bet_mint, payout.pot, payout.winner)?;
+
+        if let Some(bet_mint) = bet_mint {
+            return BetTokenAccounts::load(
+                bet_mint,
+                &self.game_vault.key(),
+                &self.winner.key(),
+                &self.bet_mint,
+                &self.winner_token_account,
+                &self.vault_token_account,
+                &self.token_program,
+            )?
+            .pay_out(
+                &self.game_vault.to_account_info(),
+                self.game_config.vault_bump,
+                payout.winner,
+            );
+        }
+
         let cpi_program = self.system_program.to_account_info();
 
         let cpi_accounts = Transfer {
@@ -50,16 +104,7 @@ impl<'info> ClaimBetAmount<'info> {
 
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer_seeds);
 
-        let bet_amount = self.pet_duel_account.bet_amount;
-
-        let deduced_amount = bet_amount
-            .checked_mul(self.game_config.fees as u64)
-            .unwrap()
-            .checked_div(100)
-            .unwrap();
-
-        let transferable_amount = bet_amount.saturating_sub(deduced_amount);
-        transfer(cpi_context, transferable_amount)?;
+        transfer(cpi_context, payout.winner)?;
 
         Ok(())
     }
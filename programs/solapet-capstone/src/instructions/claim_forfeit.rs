@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::{GameConfig, PetDuel, PetStats};
+
+#[derive(Accounts)]
+pub struct ClaimForfeit<'info> {
+    #[account(mut)]
+    pub claimant: Signer<'info>,
+
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pet_duel",
+            pet_duel_account.challenger_pet.as_ref(),
+            pet_duel_account.nonce.to_le_bytes().as_ref()
+        ],
+        bump = pet_duel_account.bump
+    )]
+    pub pet_duel_account: Account<'info, PetDuel>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.challenger_pet.as_ref()],
+        bump = challenger_pet_stats.bump
+    )]
+    pub challenger_pet_stats: Account<'info, PetStats>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.defender_pet.as_ref()],
+        bump = defender_pet_stats.bump
+    )]
+    pub defender_pet_stats: Account<'info, PetStats>,
+}
+
+impl<'info> ClaimForfeit<'info> {
+    pub fn claim_forfeit(&mut self) -> Result<()> {
+        self.pet_duel_account
+            .claim_forfeit(self.claimant.key(), self.game_config.turn_timeout)?;
+        let challenger_won = self.pet_duel_account.challenger_won();
+        self.challenger_pet_stats.finish_duel(challenger_won)?;
+        self.defender_pet_stats.finish_duel(!challenger_won)?;
+        Ok(())
+    }
+}
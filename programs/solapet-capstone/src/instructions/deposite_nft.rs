@@ -1,27 +1,42 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
 
-use mpl_token_metadata::instructions::{
-    DelegateStandardV1Cpi, DelegateStandardV1CpiAccounts, DelegateStandardV1InstructionArgs, LockV1Cpi, LockV1CpiAccounts, LockV1InstructionArgs
+use mpl_core::{
+    accounts::BaseAssetV1,
+    instructions::AddPluginV1CpiBuilder,
+    types::{FreezeDelegate, Plugin, PluginAuthority, UpdateAuthority},
 };
+use mpl_token_metadata::{
+    accounts::Metadata,
+    instructions::{
+        DelegateStakingV1Cpi, DelegateStakingV1CpiAccounts, DelegateStakingV1InstructionArgs,
+        DelegateStandardV1Cpi, DelegateStandardV1CpiAccounts, DelegateStandardV1InstructionArgs,
+        LockV1Cpi, LockV1CpiAccounts, LockV1InstructionArgs,
+    },
+};
+
+use crate::{error::ErrorCode, required_account, GameConfig, PetStandard, PetStats};
 
-use crate::{GameConfig, PetStats};
 #[derive(Accounts)]
 pub struct DepositNft<'info> {
     #[account(mut)]
     pub player: Signer<'info>,
 
-    pub collection_mint: Account<'info, Mint>, // collection_mint
+    /// CHECK: Checked against `config`; a collection account for Core pets
+    #[account(mut)]
+    pub collection_mint: UncheckedAccount<'info>, // collection_mint
 
+    /// CHECK: Checked against the pet's metadata, or read as the Core asset
     #[account(mut)]
-    pub nft_mint: Account<'info, Mint>, // the nft mint
+    pub nft_mint: UncheckedAccount<'info>, // the nft mint, or the Core asset
 
     #[account(mut)]
-    pub player_ata: Account<'info, TokenAccount>, // users token account holding pet nft
+    pub player_ata: Option<InterfaceAccount<'info, TokenAccount>>, // users token account holding pet nft
 
     #[account(
         seeds = [b"game_config"],
         bump = config.bump,
+        constraint = !config.paused @ ErrorCode::GamePaused,
         constraint = config.collection_mint.as_ref() == collection_mint.key().as_ref()
     )]
     pub config: Account<'info, GameConfig>,
@@ -29,101 +44,267 @@ pub struct DepositNft<'info> {
     /// Master edition account for the NFT
     /// CHECK: Validated by the Metaplex token metadata program
     #[account(mut)]
-    pub master_edition: UncheckedAccount<'info>,
+    pub master_edition: Option<UncheckedAccount<'info>>,
 
     /// Metadata account for the NFT
     /// CHECK: Validated by the Metaplex token metadata program
     #[account(mut)]
-    pub metadata: UncheckedAccount<'info>,
+    pub metadata: Option<UncheckedAccount<'info>>,
+
+    /// Token record for `player_ata`, required for programmable pets
+    /// CHECK: Validated by the Metaplex token metadata program
+    #[account(mut)]
+    pub token_record: Option<UncheckedAccount<'info>>,
+
+    /// Token Authorization Rules program, when the pet has a rule set
+    /// CHECK: Validated by the Metaplex token metadata program
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// The pet's rule set
+    /// CHECK: Validated by the Metaplex token metadata program
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
 
     /// The Metaplex token metadata program
     /// CHECK: This is the Metaplex Token Metadata Program
     #[account(address = mpl_token_metadata::ID)]
-    pub token_metadata_program: UncheckedAccount<'info>,
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+
+    /// The Metaplex Core program, for Core pets
+    /// CHECK: Address is validated to be the Metaplex Core program
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: Option<UncheckedAccount<'info>>,
 
     /// CHECK: Address is validated to be the instructions sysvar
     #[account(address = anchor_lang::solana_program::sysvar::instructions::id())]
     pub sysvar_instructions: UncheckedAccount<'info>,
 
     #[account(
-        init,
+        init_if_needed,
         space = PetStats::INIT_SPACE,
-        payer = player, 
-        seeds = [b"stats", player.key().as_ref()],
+        payer = player,
+        seeds = [b"stats", nft_mint.key().as_ref()],
         bump
     )]
     pub pet_stats: Account<'info, PetStats>,
 
     pub system_program: Program<'info, System>,
-    pub token_program: Program<'info, Token>,
+    #[account(address = config.token_program @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: Option<Interface<'info, TokenInterface>>,
 }
 
 impl<'info> DepositNft<'info> {
-    pub fn freeze_nft(&mut self) -> Result<()> {    
-        let master_edition_info = &self.master_edition.to_account_info();
+    pub fn freeze_nft(&mut self) -> Result<()> {
+        match self.config.pet_standard {
+            PetStandard::Core => self.freeze_core_pet(),
+            _ => self.freeze_metadata_pet(),
+        }
+    }
+
+    fn freeze_metadata_pet(&self) -> Result<()> {
+        self.verify_pet()?;
+
+        let master_edition_info = &required_account(&self.master_edition)?.to_account_info();
+        let metadata_info = &required_account(&self.metadata)?.to_account_info();
+        let token_metadata_program =
+            &required_account(&self.token_metadata_program)?.to_account_info();
         let player_info = &self.player.to_account_info();
-        let token_program_info = &self.token_program.to_account_info();
-        let player_ata_info = &self.player_ata.to_account_info();
-        
+        let token_program_info = &required_account(&self.token_program)?.to_account_info();
+        let player_ata_info = &required_account(&self.player_ata)?.to_account_info();
+
+        let token_record = self.token_record.as_ref().map(|a| a.to_account_info());
+        let authorization_rules_program = self
+            .authorization_rules_program
+            .as_ref()
+            .map(|a| a.to_account_info());
+        let authorization_rules = self
+            .authorization_rules
+            .as_ref()
+            .map(|a| a.to_account_info());
+
         let signers_seeds: &[&[&[u8]]] = &[&[b"game_config", &[self.config.bump]]];
 
-        let cpi_accounts = DelegateStandardV1CpiAccounts {
-            delegate_record: None, 
-            delegate: &self.config.to_account_info(), // give access to the config
-            metadata: &self.metadata.to_account_info(), 
-            master_edition: Some(master_edition_info), 
-            token_record: None, 
-            mint: &self.nft_mint.to_account_info(), 
-            token: player_ata_info, 
-            authority: player_info, 
-            payer: player_info, 
-            system_program: &self.system_program.to_account_info(),
-            sysvar_instructions: &self.sysvar_instructions.to_account_info(), 
-            spl_token_program: Some(token_program_info), 
-            authorization_rules_program: None,
-            authorization_rules: None, 
-        };
+        // Standard delegates cannot lock programmable NFTs; those use a staking delegate.
+        match self.config.pet_standard {
+            PetStandard::ProgrammableNonFungible => {
+                let cpi_accounts = DelegateStakingV1CpiAccounts {
+                    delegate_record: None,
+                    delegate: &self.config.to_account_info(), // give access to the config
+                    metadata: metadata_info,
+                    master_edition: Some(master_edition_info),
+                    token_record: token_record.as_ref(),
+                    mint: &self.nft_mint.to_account_info(),
+                    token: player_ata_info,
+                    authority: player_info,
+                    payer: player_info,
+                    system_program: &self.system_program.to_account_info(),
+                    sysvar_instructions: &self.sysvar_instructions.to_account_info(),
+                    spl_token_program: Some(token_program_info),
+                    authorization_rules_program: authorization_rules_program.as_ref(),
+                    authorization_rules: authorization_rules.as_ref(),
+                };
 
-        let cpi_args = DelegateStandardV1InstructionArgs {
-            amount: 1,
-        };
+                let cpi_args = DelegateStakingV1InstructionArgs {
+                    amount: 1,
+                    authorization_data: None,
+                };
+
+                DelegateStakingV1Cpi::new(token_metadata_program, cpi_accounts, cpi_args)
+                    .invoke_signed(signers_seeds)?;
+            }
+            _ => {
+                let cpi_accounts = DelegateStandardV1CpiAccounts {
+                    delegate_record: None,
+                    delegate: &self.config.to_account_info(), // give access to the config
+                    metadata: metadata_info,
+                    master_edition: Some(master_edition_info),
+                    token_record: None,
+                    mint: &self.nft_mint.to_account_info(),
+                    token: player_ata_info,
+                    authority: player_info,
+                    payer: player_info,
+                    system_program: &self.system_program.to_account_info(),
+                    sysvar_instructions: &self.sysvar_instructions.to_account_info(),
+                    spl_token_program: Some(token_program_info),
+                    authorization_rules_program: None,
+                    authorization_rules: None,
+                };
+
+                let cpi_args = DelegateStandardV1InstructionArgs { amount: 1 };
+
+                DelegateStandardV1Cpi::new(token_metadata_program, cpi_accounts, cpi_args)
+                    .invoke_signed(signers_seeds)?;
+            }
+        }
 
-        DelegateStandardV1Cpi::new(
-            &self.token_metadata_program.to_account_info(),
-            cpi_accounts,
-            cpi_args,
-        ).invoke_signed(&signers_seeds)?;
-        
         let cpi_accounts = LockV1CpiAccounts {
             mint: &self.nft_mint.to_account_info(),
             authority: &self.config.to_account_info(),
-            payer: player_info, 
+            payer: player_info,
             system_program: &self.system_program.to_account_info(),
             token_owner: Some(player_info),
-            token: player_ata_info, 
-            metadata: &self.metadata.to_account_info(),
+            token: player_ata_info,
+            metadata: metadata_info,
             edition: Some(master_edition_info),
-            token_record: None,
+            token_record: token_record.as_ref(),
             sysvar_instructions: &self.sysvar_instructions.to_account_info(),
-            spl_token_program: Some(token_program_info), 
-            authorization_rules_program: None,
-            authorization_rules: None,
+            spl_token_program: Some(token_program_info),
+            authorization_rules_program: authorization_rules_program.as_ref(),
+            authorization_rules: authorization_rules.as_ref(),
         };
 
         let cpi_args = LockV1InstructionArgs {
             authorization_data: None,
         };
 
-        LockV1Cpi::new(
-            &self.token_metadata_program.to_account_info(),
-            cpi_accounts,
-            cpi_args
-        ).invoke_signed(&signers_seeds)?;
-        
+        LockV1Cpi::new(token_metadata_program, cpi_accounts, cpi_args)
+            .invoke_signed(signers_seeds)?;
+
+        Ok(())
+    }
+
+    /// Freezes the Core asset through a freeze delegate plugin whose authority is the
+    /// `config` PDA, so only the game can thaw it.
+    fn freeze_core_pet(&self) -> Result<()> {
+        self.verify_core_pet()?;
+
+        AddPluginV1CpiBuilder::new(&required_account(&self.mpl_core_program)?.to_account_info())
+            .asset(&self.nft_mint.to_account_info())
+            .collection(Some(&self.collection_mint.to_account_info()))
+            .payer(&self.player.to_account_info())
+            .authority(Some(&self.player.to_account_info()))
+            .system_program(&self.system_program.to_account_info())
+            .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: true }))
+            .init_authority(PluginAuthority::Address {
+                address: self.config.key(),
+            })
+            .invoke()?;
+
         Ok(())
     }
 
-    pub fn init_pet_stats(&mut self, bumps: &DepositNftBumps) -> Result<()> { 
+    /// Checks that the player owns the Core asset and that it belongs to the game's
+    /// collection.
+    fn verify_core_pet(&self) -> Result<()> {
+        require_keys_eq!(
+            *self.nft_mint.owner,
+            mpl_core::ID,
+            ErrorCode::IncorrectCollectionMint
+        );
+
+        let asset = BaseAssetV1::from_bytes(&self.nft_mint.try_borrow_data()?)
+            .map_err(|_| ErrorCode::IncorrectCollectionMint)?;
+
+        require_keys_eq!(
+            asset.owner,
+            self.player.key(),
+            ErrorCode::PlayerDoesNotOwnNft
+        );
+        require!(
+            asset.update_authority == UpdateAuthority::Collection(self.config.collection_mint),
+            ErrorCode::IncorrectCollectionMint
+        );
+
+        Ok(())
+    }
+
+    /// Checks that the player holds `nft_mint` and that its metadata marks it as a
+    /// verified member of the game's collection.
+    fn verify_pet(&self) -> Result<()> {
+        let player_ata = required_account(&self.player_ata)?;
+        let metadata_info = required_account(&self.metadata)?;
+
+        require_keys_eq!(
+            player_ata.owner,
+            self.player.key(),
+            ErrorCode::PlayerDoesNotOwnNft
+        );
+        require_keys_eq!(
+            player_ata.mint,
+            self.nft_mint.key(),
+            ErrorCode::PlayerDoesNotOwnNft
+        );
+        require_eq!(player_ata.amount, 1, ErrorCode::PlayerDoesNotOwnNft);
+
+        let (metadata_address, _) = Metadata::find_pda(&self.nft_mint.key());
+        require_keys_eq!(
+            metadata_info.key(),
+            metadata_address,
+            ErrorCode::IncorrectCollectionMint
+        );
+        require_keys_eq!(
+            *metadata_info.owner,
+            mpl_token_metadata::ID,
+            ErrorCode::IncorrectCollectionMint
+        );
+
+        let metadata = Metadata::safe_deserialize(&metadata_info.try_borrow_data()?)
+            .map_err(|_| ErrorCode::IncorrectCollectionMint)?;
+
+        match metadata.collection {
+            Some(collection) if collection.verified => {
+                require_keys_eq!(
+                    collection.key,
+                    self.config.collection_mint,
+                    ErrorCode::IncorrectCollectionMint
+                );
+            }
+            _ => return err!(ErrorCode::IncorrectCollectionMint),
+        }
+
+        Ok(())
+    }
+
+    pub fn init_pet_stats(&mut self, bumps: &DepositNftBumps) -> Result<()> {
+        // A pet that was deposited before keeps its stats under the new owner.
+        if self.pet_stats.nft_mint != Pubkey::default() {
+            require_keys_eq!(
+                self.pet_stats.nft_mint,
+                self.nft_mint.key(),
+                ErrorCode::PetMintMismatch
+            );
+            return self.pet_stats.deposit(self.player.key());
+        }
+
         let now = Clock::get()?.unix_timestamp;
 
         self.pet_stats.set_inner(PetStats {
@@ -133,8 +314,18 @@ impl<'info> DepositNft<'info> {
             last_fed_timestamp: now,
             last_bathed_timestamp: now,
             last_slept_timestamp: now,
+            duel_nonce: 0,
+            active_duels: 0,
+            last_energy_decay_timestamp: now,
             bump: bumps.pet_stats,
-            // nft_mint: self.collection_mint.key()
+            version: PetStats::VERSION,
+            experience: 0,
+            level: 1,
+            wins: 0,
+            losses: 0,
+            win_streak: 0,
+            nft_mint: self.nft_mint.key(),
+            owner: self.player.key(),
         });
 
         Ok(())
@@ -1,14 +1,19 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::Mint;
+use anchor_spl::{token::ID as TOKEN_PROGRAM_ID, token_2022::ID as TOKEN_2022_PROGRAM_ID};
 
-use crate::GameConfig;
+use crate::{
+    error::ErrorCode, GameConfig, MintSettings, PetStandard, PresaleSettings,
+    DEFAULT_CHALLENGE_EXPIRY, DEFAULT_TURN_TIMEOUT, MAX_FEE_BPS,
+};
 
 #[derive(Accounts)]
 pub struct InitializeGameConfig<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
 
-    pub collection_mint: Account<'info, Mint>,
+    /// CHECK: The collection mint for Token Metadata pets, or the collection account
+    /// for Core pets; its owner is checked against `pet_standard`
+    pub collection_mint: UncheckedAccount<'info>,
 
     #[account(
         init,
@@ -29,12 +34,44 @@ pub struct InitializeGameConfig<'info> {
 }
 
 impl<'info> InitializeGameConfig<'info> {
-    pub fn init_game_config(&mut self, bumps: &InitializeGameConfigBumps, fees: u8) -> Result<()> {
+    pub fn init_game_config(
+        &mut self,
+        bumps: &InitializeGameConfigBumps,
+        fee_bps: u16,
+        pet_standard: PetStandard,
+    ) -> Result<()> {
+        let owner = *self.collection_mint.owner;
+        let owned_by_standard = match pet_standard {
+            PetStandard::Core => owner == mpl_core::ID,
+            _ => owner == TOKEN_PROGRAM_ID || owner == TOKEN_2022_PROGRAM_ID,
+        };
+        require!(owned_by_standard, ErrorCode::IncorrectCollectionMint);
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeesPercentage);
+
         self.game_config.set_inner(GameConfig {
             admin: self.admin.key(),
+            pending_admin: None,
+            fee_manager: self.admin.key(),
+            pauser: self.admin.key(),
+            metadata_updater: self.admin.key(),
+            paused: false,
             collection_mint: self.collection_mint.key(),
             game_vault: self.game_vault.key(),
-            fees,
+            fee_bps,
+            pending_fee_bps: None,
+            fee_effective_after: 0,
+            randomness_authority: None,
+            turn_timeout: DEFAULT_TURN_TIMEOUT,
+            challenge_expiry: DEFAULT_CHALLENGE_EXPIRY,
+            pets_minted: 0,
+            base_uri: String::new(),
+            mint_settings: MintSettings::default(),
+            presale: PresaleSettings::default(),
+            pet_standard,
+            token_program: owner,
+            accrued_fees: 0,
+            total_escrow: 0,
+            bet_mints: Vec::new(),
             bump: bumps.game_config,
             vault_bump: bumps.game_vault,
         });
@@ -2,8 +2,12 @@ use anchor_lang::{
     prelude::*,
     system_program::{transfer, Transfer},
 };
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
-use crate::{DuelStatus, GameConfig, PetDuel, PetStats, error::ErrorCode};
+use crate::{
+    error::ErrorCode, BetTokenAccounts, CombatStats, DuelStatus, DuelistState, GameConfig, PetDuel,
+    PetStats, RandomnessMode,
+};
 
 #[derive(Accounts)]
 pub struct InitPetDuel<'info> {
@@ -11,21 +15,26 @@ pub struct InitPetDuel<'info> {
     pub challanger: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [b"game_config"],
         bump = game_config.bump,
+        constraint = !game_config.paused @ ErrorCode::GamePaused,
         has_one = game_vault
     )]
     pub game_config: Account<'info, GameConfig>,
 
     #[account(
+        mut,
         seeds = [b"vault"],
         bump
     )]
     pub game_vault: SystemAccount<'info>,
 
     #[account(
-        seeds = [b"stats", challanger.key().as_ref()],
-        bump = pet_stats.bump
+        mut,
+        seeds = [b"stats", pet_stats.nft_mint.as_ref()],
+        bump = pet_stats.bump,
+        constraint = pet_stats.owner == challanger.key() @ ErrorCode::PlayerDoesNotOwnNft
     )]
     pub pet_stats: Account<'info, PetStats>,
 
@@ -33,34 +42,108 @@ pub struct InitPetDuel<'info> {
         init,
         payer = challanger,
         space = PetDuel::INIT_SPACE,
-        seeds = [b"pet_duel", challanger.key().as_ref()],
+        seeds = [
+            b"pet_duel",
+            pet_stats.nft_mint.as_ref(),
+            pet_stats.duel_nonce.to_le_bytes().as_ref()
+        ],
         bump
     )]
     pub pet_duel_account: Account<'info, PetDuel>,
 
+    /// SPL token of the bet; omit for SOL bets
+    pub bet_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Challenger's token account the bet is taken from
+    #[account(mut)]
+    pub challanger_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's associated token account for `bet_mint`
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> InitPetDuel<'info> {
-    pub fn initilize(&mut self, bumps: &InitPetDuelBumps, bet_amount: u64) -> Result<()> {
-        let updated_pet_energy = self.pet_stats.update_pet_energy()?;
-        require!(updated_pet_energy >= 20, ErrorCode::InsufficientPetEnergy);
+    pub fn initilize(
+        &mut self,
+        bumps: &InitPetDuelBumps,
+        bet_amount: u64,
+        randomness_mode: RandomnessMode,
+        commitment: [u8; 32],
+        opponent: Option<Pubkey>,
+    ) -> Result<()> {
+        let bet_mint = self.bet_mint.as_ref().map(|mint| mint.key());
+        if let Some(bet_mint) = bet_mint {
+            self.game_config.require_bet_mint_enabled(bet_mint)?;
+        }
+        if let Some(opponent) = opponent {
+            require_keys_neq!(
+                opponent,
+                self.challanger.key(),
+                ErrorCode::CannotChallengeSelf
+            );
+        }
+        self.pet_stats.update_pet_stats()?;
+        let challenger_combat = CombatStats::from_pet_stats(&self.pet_stats);
+        let challenger_cost = self.pet_stats.spend_duel_energy()?;
+        let nonce = self.pet_stats.start_duel()?;
+        if randomness_mode == RandomnessMode::Oracle {
+            require!(
+                self.game_config.randomness_authority.is_some(),
+                ErrorCode::RandomnessAuthorityNotSet
+            );
+        }
+        let now = Clock::get()?.unix_timestamp;
         self.pet_duel_account.set_inner(PetDuel {
             challenger: self.challanger.key(),
-            defender: Pubkey::default().key(),
+            defender: opponent.unwrap_or_default(),
             winner: Option::None,
-            challenger_pet_health: 100,
-            defender_pet_health: 100,
+            challenger_pet: self.pet_stats.nft_mint,
+            defender_pet: Pubkey::default(),
+            challenger_pet_health: challenger_combat.max_health,
+            defender_pet_health: 0,
+            challenger_combat,
+            defender_combat: CombatStats::default(),
+            challenger_state: DuelistState::default(),
+            defender_state: DuelistState::default(),
             bet_amount,
+            bet_mint,
+            fee_bps: self.game_config.current_fee_bps(now),
+            challenger_cost,
             duel_status: DuelStatus::Challenged,
             challenger_turn: true,
-            last_turn_timestamp: Clock::get()?.unix_timestamp,
+            last_turn_timestamp: now,
+            randomness_mode,
+            challenger_commitment: commitment,
+            defender_commitment: [0; 32],
+            turn: 0,
+            nonce,
             bump: bumps.pet_duel_account,
         });
         Ok(())
     }
 
     pub fn deposite(&mut self, bet_amount: u64) -> Result<()> {
+        let bet_mint = self.pet_duel_account.bet_mint;
+        self.game_config.escrow_bet(bet_mint, bet_amount)?;
+
+        if let Some(bet_mint) = bet_mint {
+            return BetTokenAccounts::load(
+                bet_mint,
+                &self.game_vault.key(),
+                &self.challanger.key(),
+                &self.bet_mint,
+                &self.challanger_token_account,
+                &self.vault_token_account,
+                &self.token_program,
+            )?
+            .deposit(&self.challanger.to_account_info(), bet_amount);
+        }
+
         let cpi_program = self.system_program.to_account_info();
 
         let cpi_accounts = Transfer {
@@ -8,9 +8,10 @@ pub struct PetInteract<'info> {
     pub player: Signer<'info>,
 
     #[account(
-        mut, 
-        seeds = [b"stats", player.key().as_ref()], 
-        bump = pet_stats.bump
+        mut,
+        seeds = [b"stats", pet_stats.nft_mint.as_ref()],
+        bump = pet_stats.bump,
+        constraint = pet_stats.owner == player.key() @ ErrorCode::PlayerDoesNotOwnNft
     )]
     pub pet_stats: Account<'info, PetStats>,
 }
@@ -18,7 +19,8 @@ pub struct PetInteract<'info> {
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub enum InteractionType {
     Feed,
-    Bath
+    Bath,
+    Sleep,
 }
 
 impl<'info> PetInteract<'info> {
@@ -26,8 +28,9 @@ impl<'info> PetInteract<'info> {
         match interaction_type {
             InteractionType::Feed => self.pet_stats.feed()?,
             InteractionType::Bath => self.pet_stats.bath()?,
+            InteractionType::Sleep => self.pet_stats.sleep()?,
             // _ => return Err(ErrorCode::InvalidPetInteraction)?,
         };
         Ok(())
     }
-}
\ No newline at end of file
+}
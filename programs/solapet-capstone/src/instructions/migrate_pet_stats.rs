@@ -0,0 +1,75 @@
+use anchor_lang::{prelude::*, solana_program::program_option::COption, system_program};
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+use crate::{error::ErrorCode, GameConfig, PetStats};
+
+#[derive(Accounts)]
+pub struct MigratePetStats<'info> {
+    #[account(mut)]
+    pub player: Signer<'info>,
+
+    #[account(
+        seeds = [b"game_config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, GameConfig>,
+
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    // The pet must still be locked in the game by this player.
+    #[account(
+        token::mint = nft_mint,
+        token::authority = player,
+        constraint = player_ata.amount == 1 @ ErrorCode::PlayerDoesNotOwnNft,
+        constraint = player_ata.is_frozen()
+            && player_ata.delegate == COption::Some(config.key()) @ ErrorCode::NftNotDeposited
+    )]
+    pub player_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Wallet-keyed stats in the original layout, which `Account<PetStats>`
+    /// cannot load; owner, size and discriminator are checked in `migrate`
+    #[account(
+        mut,
+        seeds = [b"stats", player.key().as_ref()],
+        bump
+    )]
+    pub legacy_stats: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        space = PetStats::INIT_SPACE,
+        payer = player,
+        seeds = [b"stats", nft_mint.key().as_ref()],
+        bump
+    )]
+    pub pet_stats: Account<'info, PetStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigratePetStats<'info> {
+    /// Moves legacy stats to the pet's mint-keyed address and closes the old account.
+    pub fn migrate(&mut self, bumps: &MigratePetStatsBumps) -> Result<()> {
+        let legacy = self.legacy_stats.to_account_info();
+        require_keys_eq!(*legacy.owner, crate::ID, ErrorCode::InvalidAccountState);
+
+        let pet_stats = PetStats::from_legacy(
+            &legacy.try_borrow_data()?,
+            self.nft_mint.key(),
+            self.player.key(),
+            bumps.pet_stats,
+        )?;
+        self.pet_stats.set_inner(pet_stats);
+
+        let player = self.player.to_account_info();
+        **player.try_borrow_mut_lamports()? = player
+            .lamports()
+            .checked_add(legacy.lamports())
+            .ok_or(ErrorCode::Overflow)?;
+        **legacy.try_borrow_mut_lamports()? = 0;
+        legacy.assign(&system_program::ID);
+        legacy.realloc(0, false)?;
+
+        Ok(())
+    }
+}
@@ -1,79 +1,243 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{Mint, Token},
+use anchor_lang::{
+    prelude::*,
+    solana_program::hash::{hash, hashv},
+    system_program::{transfer, Transfer},
 };
+use anchor_spl::{associated_token::AssociatedToken, token_interface::TokenInterface};
+use mpl_core::instructions::CreateV2CpiBuilder;
 use mpl_token_metadata::{
     instructions::{
         CreateV1Cpi, CreateV1CpiAccounts, CreateV1InstructionArgs, MintV1Cpi, MintV1CpiAccounts,
-        MintV1InstructionArgs,
+        MintV1InstructionArgs, VerifyCollectionV1Cpi, VerifyCollectionV1CpiAccounts,
     },
-    types::{Collection, PrintSupply, TokenStandard::NonFungible},
+    types::{Collection, PrintSupply, TokenStandard},
 };
 
-use crate::GameConfig;
+use crate::{
+    error::ErrorCode, required_account, verify_ed25519_signature, verify_merkle_proof, GameConfig,
+    MintRecord, Mood, PetGenome,
+};
 
 #[derive(Accounts)]
 pub struct MintPetNft<'info> {
     #[account(mut)]
     pub player: Signer<'info>,
 
-    pub collection_mint: Account<'info, Mint>,
+    /// CHECK: Checked against `game_config`; a collection account for Core pets
+    #[account(mut)]
+    pub collection_mint: UncheckedAccount<'info>,
 
     #[account(
+        mut,
         seeds = [b"game_config"],
         bump = game_config.bump,
+        constraint = !game_config.paused @ ErrorCode::GamePaused,
+        has_one = game_vault,
         constraint = game_config.collection_mint.as_ref() == collection_mint.key().as_ref() // Verify collection mint matches config
     )]
     pub game_config: Account<'info, GameConfig>,
 
-    /// The NFT mint account that will be initialized
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = game_config.vault_bump
+    )]
+    pub game_vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = player,
+        space = MintRecord::INIT_SPACE,
+        seeds = [b"mint_record", player.key().as_ref()],
+        bump
+    )]
+    pub mint_record: Account<'info, MintRecord>,
+
+    /// The pet's mint, or its asset for Core pets, created by the Metaplex program.
+    /// One per wallet mint count.
+    /// CHECK: Address is validated by the seeds
+    #[account(
+        mut,
+        seeds = [
+            b"nft_mint",
+            player.key().as_ref(),
+            collection_mint.key().as_ref(),
+            mint_record.minted.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub nft_mint: UncheckedAccount<'info>,
+
     #[account(
         init,
         payer = player,
-        seeds = [b"nft_mint", player.key().as_ref(), collection_mint.key().as_ref()],
-        bump,
-        mint::decimals = 0,
-        mint::authority = game_config,
-        mint::freeze_authority = game_config
+        space = PetGenome::INIT_SPACE,
+        seeds = [b"genome", nft_mint.key().as_ref()],
+        bump
     )]
-    pub nft_mint: Account<'info, Mint>,
+    pub pet_genome: Account<'info, PetGenome>,
 
     /// CHECK: This is the associated token account that will be created
     #[account(mut)]
-    pub player_token_account: UncheckedAccount<'info>,
+    pub player_token_account: Option<UncheckedAccount<'info>>,
 
     /// Metadata account for the NFT (will be populated by the token metadata program)
     /// CHECK: Validated by the Metaplex token metadata program
     #[account(mut)]
-    pub metadata: UncheckedAccount<'info>,
+    pub metadata: Option<UncheckedAccount<'info>>,
 
     /// Master edition account for the NFT
     /// CHECK: Validated by the Metaplex token metadata program
     #[account(mut)]
-    pub master_edition: UncheckedAccount<'info>,
+    pub master_edition: Option<UncheckedAccount<'info>>,
+
+    /// Token record for the player's token account, required for programmable pets
+    /// CHECK: Validated by the Metaplex token metadata program
+    #[account(mut)]
+    pub token_record: Option<UncheckedAccount<'info>>,
+
+    /// Metadata account for the collection NFT
+    /// CHECK: Validated by the Metaplex token metadata program
+    #[account(mut)]
+    pub collection_metadata: Option<UncheckedAccount<'info>>,
+
+    /// Master edition account for the collection NFT
+    /// CHECK: Validated by the Metaplex token metadata program
+    pub collection_master_edition: Option<UncheckedAccount<'info>>,
+
+    /// Collection delegate record granting `game_config` authority over the collection.
+    /// Omit when `game_config` is the collection's update authority itself.
+    /// CHECK: Validated by the Metaplex token metadata program
+    pub collection_delegate_record: Option<UncheckedAccount<'info>>,
 
     /// The Metaplex token metadata program
     /// CHECK:: metadata program
-    pub metadata_program_info: AccountInfo<'info>,
+    #[account(address = mpl_token_metadata::ID)]
+    pub metadata_program_info: Option<UncheckedAccount<'info>>,
 
-    /// Instructions sysvar account required by the Metaplex token metadata program
+    /// The Metaplex Core program, for Core pets
+    /// CHECK: Address is validated to be the Metaplex Core program
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: Option<UncheckedAccount<'info>>,
+
+    /// Instructions sysvar account, read for the genome signature and required by the
+    /// Metaplex token metadata program
     #[account(address = anchor_lang::solana_program::sysvar::instructions::id())]
     /// CHECK: Address is validated to be the instructions sysvar
     pub sysvar_instructions: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    #[account(address = game_config.token_program @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> MintPetNft<'info> {
-    pub fn mint_pet_nft(&mut self, uri: String) -> Result<()> {
-        let spl_token_program = &self.token_program.to_account_info();
+    /// Applies the presale rules when a proof is given and the public rules otherwise,
+    /// returning the price to pay.
+    pub fn record_mint(
+        &mut self,
+        bumps: &MintPetNftBumps,
+        proof: Option<Vec<[u8; 32]>>,
+    ) -> Result<u64> {
+        let now = Clock::get()?.unix_timestamp;
+        self.mint_record.player = self.player.key();
+        self.mint_record.bump = bumps.mint_record;
+
+        match proof {
+            Some(proof) => {
+                let presale = self.game_config.presale;
+                presale.require_active(now)?;
+                require!(
+                    verify_merkle_proof(
+                        &proof,
+                        &presale.merkle_root,
+                        hash(self.player.key().as_ref()).to_bytes()
+                    ),
+                    ErrorCode::NotOnAllowlist
+                );
+                self.mint_record
+                    .record_presale_mint(presale.max_per_wallet)?;
+                Ok(presale.price)
+            }
+            None => {
+                let mint_settings = self.game_config.mint_settings;
+                mint_settings.require_open(now)?;
+                self.mint_record.record_mint(mint_settings.max_per_wallet)?;
+                Ok(mint_settings.price)
+            }
+        }
+    }
+
+    /// `randomness` is the randomness authority's signature over the pet's
+    /// `PetGenome::oracle_message`, verified from a preceding Ed25519 instruction.
+    pub fn mint_pet_nft(&mut self, bumps: &MintPetNftBumps, randomness: &[u8]) -> Result<()> {
+        let authority = self
+            .game_config
+            .randomness_authority
+            .ok_or(ErrorCode::RandomnessAuthorityNotSet)?;
+        verify_ed25519_signature(
+            &self.sysvar_instructions,
+            randomness,
+            &authority,
+            &PetGenome::oracle_message(&self.nft_mint.key()),
+        )?;
+
+        let number = self.game_config.next_pet_number()?;
+        // Only signed inputs go into the seed; mixing in the mint order would let
+        // the minter time their mint for a better roll.
+        let seed = hashv(&[randomness, self.nft_mint.key().as_ref()]);
+        self.pet_genome.set_inner(PetGenome::generate(
+            self.nft_mint.key(),
+            number,
+            &seed.to_bytes(),
+            bumps.pet_genome,
+        )?);
+
+        let name = GameConfig::pet_name(number);
+        let uri = self.game_config.pet_uri(number, 1, Mood::Happy);
+
+        // `record_mint` has already counted this pet, so its seed index is one back.
+        let index = (self.mint_record.minted - 1).to_le_bytes();
+        let player_key = self.player.key();
+        let collection_key = self.collection_mint.key();
+        let signers_seeds: &[&[&[u8]]] = &[
+            &[b"game_config", &[self.game_config.bump]],
+            &[
+                b"nft_mint",
+                player_key.as_ref(),
+                collection_key.as_ref(),
+                index.as_ref(),
+                &[bumps.nft_mint],
+            ],
+        ];
+
+        match self.game_config.pet_standard.token_standard() {
+            Some(token_standard) => {
+                self.mint_metadata_pet(name, uri, token_standard, signers_seeds)
+            }
+            None => self.mint_core_pet(name, uri, signers_seeds),
+        }
+    }
+
+    /// Creates the pet with Token Metadata, mints it to the player and verifies it
+    /// into the collection.
+    fn mint_metadata_pet(
+        &self,
+        name: String,
+        uri: String,
+        token_standard: TokenStandard,
+        signers_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        let metadata_program = &required_account(&self.metadata_program_info)?.to_account_info();
+        let metadata = &required_account(&self.metadata)?.to_account_info();
+        let master_edition = &required_account(&self.master_edition)?.to_account_info();
+        let spl_token_program = &required_account(&self.token_program)?.to_account_info();
 
         let cpi_accounts = CreateV1CpiAccounts {
-            metadata: &self.metadata.to_account_info(),
-            master_edition: Some(&self.master_edition),
-            mint: (&self.nft_mint.to_account_info(), false),
+            metadata,
+            master_edition: Some(master_edition),
+            mint: (&self.nft_mint.to_account_info(), true),
             authority: &self.game_config.to_account_info(),
             payer: &self.player.to_account_info(),
             update_authority: (&self.game_config.to_account_info(), false),
@@ -83,14 +247,14 @@ impl<'info> MintPetNft<'info> {
         };
 
         let cpi_args = CreateV1InstructionArgs {
-            name: String::from("pet"),
+            name,
             symbol: String::from("pet"),
             uri,
             seller_fee_basis_points: 0,
             creators: None,
             primary_sale_happened: false,
             is_mutable: true,
-            token_standard: NonFungible,
+            token_standard,
             collection: Some(Collection {
                 key: self.collection_mint.key(),
                 verified: false,
@@ -102,27 +266,27 @@ impl<'info> MintPetNft<'info> {
             print_supply: Some(PrintSupply::Zero),
         };
 
-        let signers_seeds: &[&[&[u8]]] = &[&[b"game_config", &[self.game_config.bump]]];
-
-        CreateV1Cpi::new(&self.metadata_program_info, cpi_accounts, cpi_args)
-            .invoke_signed(signers_seeds)?;
+        CreateV1Cpi::new(metadata_program, cpi_accounts, cpi_args).invoke_signed(signers_seeds)?;
 
         let player = &self.player.to_account_info();
-        let master_edition = &self.master_edition.to_account_info();
+        let token_record = self
+            .token_record
+            .as_ref()
+            .map(|record| record.to_account_info());
 
         let mint_accounts = MintV1CpiAccounts {
-            token: &self.player_token_account.to_account_info(),
+            token: &required_account(&self.player_token_account)?.to_account_info(),
             token_owner: Some(player),
-            metadata: &self.metadata.to_account_info(),
+            metadata,
             master_edition: Some(master_edition),
-            token_record: None,
+            token_record: token_record.as_ref(),
             mint: &self.nft_mint.to_account_info(),
             authority: &self.game_config.to_account_info(),
             delegate_record: None,
-            payer: &self.player.to_account_info(),
+            payer: player,
             system_program: &self.system_program.to_account_info(),
-            spl_token_program: spl_token_program,
-            spl_ata_program: &self.associated_token_program.to_account_info(),
+            spl_token_program,
+            spl_ata_program: &required_account(&self.associated_token_program)?.to_account_info(),
             authorization_rules_program: None,
             authorization_rules: None,
             sysvar_instructions: &self.sysvar_instructions.to_account_info(),
@@ -133,8 +297,65 @@ impl<'info> MintPetNft<'info> {
             authorization_data: None,
         };
 
-        MintV1Cpi::new(&self.metadata_program_info, mint_accounts, mint_cpi_args)
+        MintV1Cpi::new(metadata_program, mint_accounts, mint_cpi_args)
+            .invoke_signed(signers_seeds)?;
+
+        let collection_delegate_record = self
+            .collection_delegate_record
+            .as_ref()
+            .map(|record| record.to_account_info());
+
+        let verify_accounts = VerifyCollectionV1CpiAccounts {
+            authority: &self.game_config.to_account_info(),
+            delegate_record: collection_delegate_record.as_ref(),
+            metadata,
+            collection_mint: &self.collection_mint.to_account_info(),
+            collection_metadata: Some(
+                &required_account(&self.collection_metadata)?.to_account_info(),
+            ),
+            collection_master_edition: Some(
+                &required_account(&self.collection_master_edition)?.to_account_info(),
+            ),
+            system_program: &self.system_program.to_account_info(),
+            sysvar_instructions: &self.sysvar_instructions.to_account_info(),
+        };
+
+        VerifyCollectionV1Cpi::new(metadata_program, verify_accounts)
             .invoke_signed(signers_seeds)?;
         Ok(())
     }
+
+    /// Creates the pet as a Core asset in the collection, owned by the player.
+    /// `game_config` must be the collection's update authority or an update delegate.
+    fn mint_core_pet(&self, name: String, uri: String, signers_seeds: &[&[&[u8]]]) -> Result<()> {
+        CreateV2CpiBuilder::new(&required_account(&self.mpl_core_program)?.to_account_info())
+            .asset(&self.nft_mint.to_account_info())
+            .collection(Some(&self.collection_mint.to_account_info()))
+            .authority(Some(&self.game_config.to_account_info()))
+            .payer(&self.player.to_account_info())
+            .owner(Some(&self.player.to_account_info()))
+            .system_program(&self.system_program.to_account_info())
+            .name(name)
+            .uri(uri)
+            .invoke_signed(signers_seeds)?;
+        Ok(())
+    }
+
+    pub fn pay_mint_price(&mut self, price: u64) -> Result<()> {
+        if price == 0 {
+            return Ok(());
+        }
+
+        let cpi_accounts = Transfer {
+            from: self.player.to_account_info(),
+            to: self.game_vault.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), cpi_accounts);
+
+        transfer(cpi_ctx, price)?;
+        self.game_config.accrue_fees(None, price)?;
+
+        Ok(())
+    }
 }
@@ -4,6 +4,42 @@ pub use init_config::*;
 pub mod update_fees;
 pub use update_fees::*;
 
+pub mod update_randomness_authority;
+pub use update_randomness_authority::*;
+
+pub mod update_turn_timeout;
+pub use update_turn_timeout::*;
+
+pub mod update_challenge_expiry;
+pub use update_challenge_expiry::*;
+
+pub mod update_base_uri;
+pub use update_base_uri::*;
+
+pub mod update_mint_settings;
+pub use update_mint_settings::*;
+
+pub mod update_presale;
+pub use update_presale::*;
+
+pub mod withdraw_fees;
+pub use withdraw_fees::*;
+
+pub mod update_bet_mint;
+pub use update_bet_mint::*;
+
+pub mod propose_admin;
+pub use propose_admin::*;
+
+pub mod accept_admin;
+pub use accept_admin::*;
+
+pub mod update_role;
+pub use update_role::*;
+
+pub mod set_paused;
+pub use set_paused::*;
+
 pub mod mint_pet_nft;
 pub use mint_pet_nft::*;
 
@@ -13,6 +49,12 @@ pub use deposite_nft::*;
 pub mod interact;
 pub use interact::*;
 
+pub mod refresh_pet_metadata;
+pub use refresh_pet_metadata::*;
+
+pub mod migrate_pet_stats;
+pub use migrate_pet_stats::*;
+
 pub mod withdraw_nft;
 pub use withdraw_nft::*;
 
@@ -22,8 +64,17 @@ pub use init_pet_duel::*;
 pub mod accept_pet_duel;
 pub use accept_pet_duel::*;
 
+pub mod cancel_pet_duel;
+pub use cancel_pet_duel::*;
+
 pub mod perfrom_attacks;
 pub use perfrom_attacks::*;
 
 pub mod claim_bet;
-pub use claim_bet::*;
\ No newline at end of file
+pub use claim_bet::*;
+
+pub mod claim_forfeit;
+pub use claim_forfeit::*;
+
+pub mod surrender_pet_duel;
+pub use surrender_pet_duel::*;
@@ -1,29 +1,64 @@
 use anchor_lang::prelude::*;
 
-use crate::{error::ErrorCode, PetDuel};
+use crate::{
+    error::ErrorCode, verify_ed25519_signature, DuelStatus, GameConfig, PetDuel, PetStats,
+    RandomnessMode,
+};
 
 #[derive(Accounts)]
 pub struct PetAttack<'info> {
     #[account(mut)]
     pub attacker: Signer<'info>,
 
-    /// CHECK: ?
-    pub challanger: AccountInfo<'info>,
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
 
     #[account(
         mut,
-        seeds = [b"pet_duel", challanger.key().as_ref()],
+        seeds = [
+            b"pet_duel",
+            pet_duel_account.challenger_pet.as_ref(),
+            pet_duel_account.nonce.to_le_bytes().as_ref()
+        ],
         bump = pet_duel_account.bump
     )]
     pub pet_duel_account: Account<'info, PetDuel>,
 
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.challenger_pet.as_ref()],
+        bump = challenger_pet_stats.bump
+    )]
+    pub challenger_pet_stats: Account<'info, PetStats>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.defender_pet.as_ref()],
+        bump = defender_pet_stats.bump
+    )]
+    pub defender_pet_stats: Account<'info, PetStats>,
+
     pub system_program: Program<'info, System>,
-    /// CHECK: This is the instructions sysvar account
+    /// CHECK: Address is validated to be the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::id())]
     pub instructions_sysvar: AccountInfo<'info>,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub enum DuelAction {
+    Attack,
+    Guard,
+    Special,
+    Heal,
+}
+
 impl<'info> PetAttack<'info> {
-    pub fn attack(&mut self, sig: &[u8]) -> Result<()> {
+    pub fn attack(&mut self, randomness: &[u8], action: DuelAction) -> Result<()> {
+        self.pet_duel_account.require_started()?;
+
         if self.pet_duel_account.challenger_turn {
             require_keys_eq!(
                 self.pet_duel_account.challenger,
@@ -38,10 +73,50 @@ impl<'info> PetAttack<'info> {
             );
         }
 
-        self.pet_duel_account.perform_attack(&sig)?;
+        let seed = self.damage_seed(randomness)?;
+
+        self.pet_duel_account.begin_turn(&action)?;
+
+        match action {
+            DuelAction::Attack => self.pet_duel_account.perform_attack(&seed)?,
+            DuelAction::Guard => self.pet_duel_account.guard()?,
+            DuelAction::Special => self.pet_duel_account.perform_special(&seed)?,
+            DuelAction::Heal => self.pet_duel_account.heal(&seed)?,
+        };
+
+        if self.pet_duel_account.duel_status == DuelStatus::Finished {
+            let challenger_won = self.pet_duel_account.challenger_won();
+            self.challenger_pet_stats.finish_duel(challenger_won)?;
+            self.defender_pet_stats.finish_duel(!challenger_won)?;
+        }
 
         self.pet_duel_account.next_turn()?;
 
         Ok(())
     }
+
+    fn damage_seed(&mut self, randomness: &[u8]) -> Result<Vec<u8>> {
+        match self.pet_duel_account.randomness_mode {
+            RandomnessMode::CommitReveal => {
+                Ok(self.pet_duel_account.reveal_seed(randomness)?.to_vec())
+            }
+            RandomnessMode::Oracle => {
+                let authority = self
+                    .game_config
+                    .randomness_authority
+                    .ok_or(ErrorCode::RandomnessAuthorityNotSet)?;
+                let message = self
+                    .pet_duel_account
+                    .oracle_message(&self.pet_duel_account.key(), &self.attacker.key());
+
+                verify_ed25519_signature(
+                    &self.instructions_sysvar,
+                    randomness,
+                    &authority,
+                    &message,
+                )?;
+                Ok(randomness.to_vec())
+            }
+        }
+    }
 }
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig};
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> ProposeAdmin<'info> {
+    pub fn propose_admin(&mut self, new_admin: Option<Pubkey>) -> Result<()> {
+        self.game_config.propose_admin(new_admin)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+use mpl_token_metadata::{
+    instructions::{UpdateV1Cpi, UpdateV1CpiAccounts, UpdateV1InstructionArgs},
+    types::{CollectionDetailsToggle, CollectionToggle, Data, RuleSetToggle, UsesToggle},
+};
+
+use crate::{GameConfig, PetGenome, PetStats};
+
+#[derive(Accounts)]
+pub struct RefreshPetMetadata<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"game_config"],
+        bump = game_config.bump
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    pub nft_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", nft_mint.key().as_ref()],
+        bump = pet_stats.bump
+    )]
+    pub pet_stats: Account<'info, PetStats>,
+
+    #[account(
+        seeds = [b"genome", nft_mint.key().as_ref()],
+        bump = pet_genome.bump
+    )]
+    pub pet_genome: Account<'info, PetGenome>,
+
+    /// Metadata account for the NFT
+    /// CHECK: Validated by the Metaplex token metadata program
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>,
+
+    /// The Metaplex token metadata program
+    /// CHECK: This is the Metaplex Token Metadata Program
+    #[account(address = mpl_token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+
+    /// CHECK: Address is validated to be the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::id())]
+    pub sysvar_instructions: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefreshPetMetadata<'info> {
+    /// Rewrites the pet's name and URI from its on-chain state. Anyone may crank
+    /// it, since the result only depends on program-owned accounts.
+    pub fn refresh_metadata(&mut self) -> Result<()> {
+        self.pet_stats.update_pet_stats()?;
+        let number = self.pet_genome.number;
+
+        let cpi_accounts = UpdateV1CpiAccounts {
+            authority: &self.game_config.to_account_info(),
+            delegate_record: None,
+            token: None,
+            mint: &self.nft_mint.to_account_info(),
+            metadata: &self.metadata.to_account_info(),
+            edition: None,
+            payer: &self.payer.to_account_info(),
+            system_program: &self.system_program.to_account_info(),
+            sysvar_instructions: &self.sysvar_instructions.to_account_info(),
+            authorization_rules_program: None,
+            authorization_rules: None,
+        };
+
+        let cpi_args = UpdateV1InstructionArgs {
+            new_update_authority: None,
+            data: Some(Data {
+                name: GameConfig::pet_name(number),
+                symbol: String::from("pet"),
+                uri: self
+                    .game_config
+                    .pet_uri(number, self.pet_stats.level, self.pet_stats.mood()),
+                seller_fee_basis_points: 0,
+                creators: None,
+            }),
+            primary_sale_happened: None,
+            is_mutable: None,
+            collection: CollectionToggle::None,
+            collection_details: CollectionDetailsToggle::None,
+            uses: UsesToggle::None,
+            rule_set: RuleSetToggle::None,
+            authorization_data: None,
+        };
+
+        let signers_seeds: &[&[&[u8]]] = &[&[b"game_config", &[self.game_config.bump]]];
+
+        UpdateV1Cpi::new(
+            &self.token_metadata_program.to_account_info(),
+            cpi_accounts,
+            cpi_args,
+        )
+        .invoke_signed(signers_seeds)?;
+
+        Ok(())
+    }
+}
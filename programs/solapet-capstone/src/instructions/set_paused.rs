@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig};
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(mut)]
+    pub pauser: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = pauser @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> SetPaused<'info> {
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        self.game_config.set_paused(paused)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{PetDuel, PetStats};
+
+#[derive(Accounts)]
+pub struct SurrenderPetDuel<'info> {
+    pub player: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"pet_duel",
+            pet_duel_account.challenger_pet.as_ref(),
+            pet_duel_account.nonce.to_le_bytes().as_ref()
+        ],
+        bump = pet_duel_account.bump
+    )]
+    pub pet_duel_account: Account<'info, PetDuel>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.challenger_pet.as_ref()],
+        bump = challenger_pet_stats.bump
+    )]
+    pub challenger_pet_stats: Account<'info, PetStats>,
+
+    #[account(
+        mut,
+        seeds = [b"stats", pet_duel_account.defender_pet.as_ref()],
+        bump = defender_pet_stats.bump
+    )]
+    pub defender_pet_stats: Account<'info, PetStats>,
+}
+
+impl<'info> SurrenderPetDuel<'info> {
+    pub fn surrender(&mut self) -> Result<()> {
+        self.pet_duel_account.surrender(self.player.key())?;
+        let challenger_won = self.pet_duel_account.challenger_won();
+        self.challenger_pet_stats.finish_duel(challenger_won)?;
+        self.defender_pet_stats.finish_duel(!challenger_won)?;
+        Ok(())
+    }
+}
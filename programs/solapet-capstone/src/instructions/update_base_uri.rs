@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig};
+
+#[derive(Accounts)]
+pub struct UpdateBaseUri<'info> {
+    #[account(mut)]
+    pub metadata_updater: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = metadata_updater @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> UpdateBaseUri<'info> {
+    pub fn update_base_uri(&mut self, base_uri: String) -> Result<()> {
+        self.game_config.update_base_uri(base_uri)?;
+        Ok(())
+    }
+}
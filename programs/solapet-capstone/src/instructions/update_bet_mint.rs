@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    },
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{error::ErrorCode, GameConfig};
+
+#[derive(Accounts)]
+pub struct UpdateBetMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess,
+        has_one = game_vault
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        seeds = [b"vault"],
+        bump = game_config.vault_bump
+    )]
+    pub game_vault: SystemAccount<'info>,
+
+    pub bet_mint: InterfaceAccount<'info, Mint>,
+
+    /// Holds the escrowed bets and fees for `bet_mint`
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = bet_mint,
+        associated_token::authority = game_vault,
+        associated_token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateBetMint<'info> {
+    pub fn update_bet_mint(&mut self, enabled: bool) -> Result<()> {
+        if enabled {
+            self.require_no_transfer_fee()?;
+        }
+        self.game_config
+            .set_bet_mint(self.bet_mint.key(), enabled)?;
+        Ok(())
+    }
+
+    /// Escrow is credited with the full bet, so a Token-2022 mint that withholds a
+    /// fee on every transfer would leave the vault short of the pots it owes.
+    fn require_no_transfer_fee(&self) -> Result<()> {
+        let mint_info = self.bet_mint.to_account_info();
+        if *mint_info.owner != spl_token_2022::ID {
+            return Ok(());
+        }
+
+        let data = mint_info.try_borrow_data()?;
+        let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+        require!(
+            !mint
+                .get_extension_types()?
+                .contains(&ExtensionType::TransferFeeConfig),
+            ErrorCode::TransferFeeMintNotSupported
+        );
+        Ok(())
+    }
+}
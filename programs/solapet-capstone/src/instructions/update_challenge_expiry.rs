@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig};
+
+#[derive(Accounts)]
+pub struct UpdateChallengeExpiry<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> UpdateChallengeExpiry<'info> {
+    pub fn update_challenge_expiry(&mut self, challenge_expiry: i64) -> Result<()> {
+        self.game_config.update_challenge_expiry(challenge_expiry)?;
+        Ok(())
+    }
+}
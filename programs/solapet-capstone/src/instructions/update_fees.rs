@@ -5,20 +5,21 @@ use crate::{error::ErrorCode, GameConfig};
 #[derive(Accounts)]
 pub struct UpdateFees<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub fee_manager: Signer<'info>,
 
     #[account(
         mut,
         seeds = [b"game_config"],
         bump = game_config.bump,
-        has_one = admin @ ErrorCode::UnauthorizedAction
+        has_one = fee_manager @ ErrorCode::InvalidAdminAccess
     )]
     pub game_config: Account<'info, GameConfig>,
 }
 
 impl<'info> UpdateFees<'info> {
-    pub fn update_fees(&mut self, new_fees: u8) -> Result<()> {
-        self.game_config.update_fees(new_fees)?;
+    pub fn update_fees(&mut self, fee_bps: u16) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.game_config.update_fees(fee_bps, now)?;
         Ok(())
     }
 }
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig, MintSettings};
+
+#[derive(Accounts)]
+pub struct UpdateMintSettings<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> UpdateMintSettings<'info> {
+    pub fn update_mint_settings(&mut self, mint_settings: MintSettings) -> Result<()> {
+        self.game_config.update_mint_settings(mint_settings)?;
+        Ok(())
+    }
+}
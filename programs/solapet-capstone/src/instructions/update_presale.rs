@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig, PresaleSettings};
+
+#[derive(Accounts)]
+pub struct UpdatePresale<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> UpdatePresale<'info> {
+    pub fn update_presale(&mut self, presale: PresaleSettings) -> Result<()> {
+        self.game_config.update_presale(presale)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig};
+
+#[derive(Accounts)]
+pub struct UpdateRandomnessAuthority<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> UpdateRandomnessAuthority<'info> {
+    pub fn update_randomness_authority(&mut self, authority: Option<Pubkey>) -> Result<()> {
+        self.game_config.update_randomness_authority(authority)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, AdminRole, GameConfig};
+
+#[derive(Accounts)]
+pub struct UpdateRole<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> UpdateRole<'info> {
+    pub fn update_role(&mut self, role: AdminRole, authority: Pubkey) -> Result<()> {
+        self.game_config.update_role(role, authority)?;
+        Ok(())
+    }
+}
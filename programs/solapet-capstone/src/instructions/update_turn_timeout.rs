@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, GameConfig};
+
+#[derive(Accounts)]
+pub struct UpdateTurnTimeout<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = admin @ ErrorCode::InvalidAdminAccess
+    )]
+    pub game_config: Account<'info, GameConfig>,
+}
+
+impl<'info> UpdateTurnTimeout<'info> {
+    pub fn update_turn_timeout(&mut self, turn_timeout: i64) -> Result<()> {
+        self.game_config.update_turn_timeout(turn_timeout)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,90 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{error::ErrorCode, BetTokenAccounts, GameConfig};
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(mut)]
+    pub fee_manager: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"game_config"],
+        bump = game_config.bump,
+        has_one = fee_manager @ ErrorCode::InvalidAdminAccess,
+        has_one = game_vault
+    )]
+    pub game_config: Account<'info, GameConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"vault"],
+        bump = game_config.vault_bump
+    )]
+    pub game_vault: SystemAccount<'info>,
+
+    /// SPL token to withdraw fees in; omit for SOL
+    pub bet_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    /// Fee manager's token account the fees are paid to
+    #[account(mut)]
+    pub fee_manager_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The vault's associated token account for `bet_mint`
+    #[account(mut)]
+    pub vault_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawFees<'info> {
+    pub fn withdraw_fees(&mut self, amount: u64) -> Result<()> {
+        if let Some(bet_mint) = self.bet_mint.as_ref().map(|mint| mint.key()) {
+            let bet_accounts = BetTokenAccounts::load(
+                bet_mint,
+                &self.game_vault.key(),
+                &self.fee_manager.key(),
+                &self.bet_mint,
+                &self.fee_manager_token_account,
+                &self.vault_token_account,
+                &self.token_program,
+            )?;
+            self.game_config.withdraw_fees(
+                Some(bet_mint),
+                amount,
+                bet_accounts.vault_token_account.amount,
+            )?;
+            return bet_accounts.pay_out(
+                &self.game_vault.to_account_info(),
+                self.game_config.vault_bump,
+                amount,
+            );
+        }
+
+        self.game_config
+            .withdraw_fees(None, amount, self.game_vault.lamports())?;
+
+        let cpi_accounts = Transfer {
+            from: self.game_vault.to_account_info(),
+            to: self.fee_manager.to_account_info(),
+        };
+
+        let signer_seeds: &[&[&[u8]]] = &[&[b"vault", &[self.game_config.vault_bump]]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.system_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+
+        transfer(cpi_ctx, amount)?;
+
+        Ok(())
+    }
+}
@@ -1,24 +1,31 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+use mpl_core::{
+    instructions::{RemovePluginV1CpiBuilder, UpdatePluginV1CpiBuilder},
+    types::{FreezeDelegate, Plugin, PluginType},
+};
 use mpl_token_metadata::instructions::{
-    RevokeStandardV1Cpi, RevokeStandardV1CpiAccounts, UnlockV1Cpi, UnlockV1CpiAccounts,
-    UnlockV1InstructionArgs,
+    RevokeStakingV1Cpi, RevokeStakingV1CpiAccounts, RevokeStandardV1Cpi,
+    RevokeStandardV1CpiAccounts, UnlockV1Cpi, UnlockV1CpiAccounts, UnlockV1InstructionArgs,
 };
 
-use crate::{GameConfig, PetStats};
+use crate::{error::ErrorCode, required_account, GameConfig, PetStandard, PetStats};
 
 #[derive(Accounts)]
 pub struct WithdrawNFT<'info> {
     #[account(mut)]
     pub player: Signer<'info>,
 
-    pub collection_mint: Account<'info, Mint>, // collection_mint
+    /// CHECK: Checked against `config`; a collection account for Core pets
+    #[account(mut)]
+    pub collection_mint: UncheckedAccount<'info>, // collection_mint
 
+    /// CHECK: Bound to `pet_stats`; the Core asset for Core pets
     #[account(mut)]
-    pub nft_mint: Account<'info, Mint>, // the nft mint
+    pub nft_mint: UncheckedAccount<'info>, // the nft mint, or the Core asset
 
     #[account(mut)]
-    pub player_ata: Account<'info, TokenAccount>, // users token account holding pet nft
+    pub player_ata: Option<InterfaceAccount<'info, TokenAccount>>, // users token account holding pet nft
 
     #[account(
         seeds = [b"game_config"],
@@ -30,12 +37,12 @@ pub struct WithdrawNFT<'info> {
     /// Master edition account for the NFT
     /// CHECK: Validated by the Metaplex token metadata program
     #[account(mut)]
-    pub master_edition: UncheckedAccount<'info>,
+    pub master_edition: Option<UncheckedAccount<'info>>,
 
     /// Metadata account for the NFT
     /// CHECK: Validated by the Metaplex token metadata program
     #[account(mut)]
-    pub metadata: UncheckedAccount<'info>,
+    pub metadata: Option<UncheckedAccount<'info>>,
 
     /// CHECK: Address is validated to be the instructions sysvar
     #[account(address = anchor_lang::solana_program::sysvar::instructions::id())]
@@ -43,77 +50,167 @@ pub struct WithdrawNFT<'info> {
 
     #[account(
         mut,
-        close = player,
-        seeds = [b"stats", player.key().as_ref()],
-        bump
+        seeds = [b"stats", nft_mint.key().as_ref()],
+        bump = pet_stats.bump,
+        has_one = nft_mint @ ErrorCode::PetMintMismatch,
+        constraint = pet_stats.owner == player.key() @ ErrorCode::PlayerDoesNotOwnNft
     )]
     pub pet_stats: Account<'info, PetStats>,
 
+    /// Token record for `player_ata`, required for programmable pets
+    /// CHECK: Validated by the Metaplex token metadata program
+    #[account(mut)]
+    pub token_record: Option<UncheckedAccount<'info>>,
+
+    /// Token Authorization Rules program, when the pet has a rule set
+    /// CHECK: Validated by the Metaplex token metadata program
+    pub authorization_rules_program: Option<UncheckedAccount<'info>>,
+
+    /// The pet's rule set
+    /// CHECK: Validated by the Metaplex token metadata program
+    pub authorization_rules: Option<UncheckedAccount<'info>>,
+
     /// The Metaplex token metadata program
     /// CHECK: This is the Metaplex Token Metadata Program
     #[account(address = mpl_token_metadata::ID)]
-    pub token_metadata_program: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
+    pub token_metadata_program: Option<UncheckedAccount<'info>>,
+
+    /// The Metaplex Core program, for Core pets
+    /// CHECK: Address is validated to be the Metaplex Core program
+    #[account(address = mpl_core::ID)]
+    pub mpl_core_program: Option<UncheckedAccount<'info>>,
+    #[account(address = config.token_program @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: Option<Interface<'info, TokenInterface>>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> WithdrawNFT<'info> {
     pub fn withdraw(&mut self) -> Result<()> {
-        let player_ata = &self.player_ata.to_account_info();
-        let master_edition = &self.master_edition.to_account_info();
-        let metadata = &self.metadata.to_account_info();
+        let signers_seeds: &[&[&[u8]]] = &[&[b"game_config", &[self.config.bump]]];
+
+        match self.config.pet_standard {
+            PetStandard::Core => self.thaw_core_pet(signers_seeds)?,
+            _ => self.thaw_metadata_pet(signers_seeds)?,
+        }
+
+        self.pet_stats.withdraw()?;
+
+        Ok(())
+    }
+
+    /// Thaws the Core asset, then lets the player remove the now unfrozen plugin so
+    /// the pet can be deposited again later.
+    fn thaw_core_pet(&self, signers_seeds: &[&[&[u8]]]) -> Result<()> {
+        let mpl_core_program = &required_account(&self.mpl_core_program)?.to_account_info();
+
+        UpdatePluginV1CpiBuilder::new(mpl_core_program)
+            .asset(&self.nft_mint.to_account_info())
+            .collection(Some(&self.collection_mint.to_account_info()))
+            .payer(&self.player.to_account_info())
+            .authority(Some(&self.config.to_account_info()))
+            .system_program(&self.system_program.to_account_info())
+            .plugin(Plugin::FreezeDelegate(FreezeDelegate { frozen: false }))
+            .invoke_signed(signers_seeds)?;
+
+        RemovePluginV1CpiBuilder::new(mpl_core_program)
+            .asset(&self.nft_mint.to_account_info())
+            .collection(Some(&self.collection_mint.to_account_info()))
+            .payer(&self.player.to_account_info())
+            .authority(Some(&self.player.to_account_info()))
+            .system_program(&self.system_program.to_account_info())
+            .plugin_type(PluginType::FreezeDelegate)
+            .invoke()?;
+
+        Ok(())
+    }
+
+    fn thaw_metadata_pet(&self, signers_seeds: &[&[&[u8]]]) -> Result<()> {
+        let player_ata = &required_account(&self.player_ata)?.to_account_info();
+        let master_edition = &required_account(&self.master_edition)?.to_account_info();
+        let metadata = &required_account(&self.metadata)?.to_account_info();
+        let token_metadata_program =
+            &required_account(&self.token_metadata_program)?.to_account_info();
         let sysvar_instructions = &self.sysvar_instructions.to_account_info();
-        let token_program = &self.token_program.to_account_info();
+        let token_program = &required_account(&self.token_program)?.to_account_info();
+        let player = &self.player.to_account_info();
+        let token_record = self.token_record.as_ref().map(|a| a.to_account_info());
+        let authorization_rules_program = self
+            .authorization_rules_program
+            .as_ref()
+            .map(|a| a.to_account_info());
+        let authorization_rules = self
+            .authorization_rules
+            .as_ref()
+            .map(|a| a.to_account_info());
 
         let cpi_accounts = UnlockV1CpiAccounts {
             mint: &self.nft_mint.to_account_info(),
             token: player_ata,
-            token_owner: Some(player_ata),
-            token_record: None,
+            token_owner: Some(player),
+            token_record: token_record.as_ref(),
             edition: Some(master_edition),
-            metadata: metadata,
+            metadata,
             authority: &self.config.to_account_info(),
-            payer: &self.player.to_account_info(),
+            payer: player,
             system_program: &self.system_program.to_account_info(),
-            sysvar_instructions: sysvar_instructions,
+            sysvar_instructions,
             spl_token_program: Some(token_program),
-            authorization_rules_program: None,
-            authorization_rules: None,
+            authorization_rules_program: authorization_rules_program.as_ref(),
+            authorization_rules: authorization_rules.as_ref(),
         };
         let cpi_args = UnlockV1InstructionArgs {
             authorization_data: None,
         };
 
-        let signers_seeds: &[&[&[u8]]] = &[&[b"game_config", &[self.config.bump]]];
-
-        UnlockV1Cpi::new(
-            &self.token_metadata_program.to_account_info(),
-            cpi_accounts,
-            cpi_args,
-        )
-        .invoke_signed(signers_seeds)?;
-
-        // Revoke the delegate standard v1
-        let cpi_accounts = RevokeStandardV1CpiAccounts {
-            delegate_record: None,
-            delegate: &self.config.to_account_info(),
-            metadata: metadata,
-            master_edition: Some(master_edition),
-            token_record: None,
-            mint: &self.nft_mint.to_account_info(),
-            token: player_ata,
-            authority: &self.player.to_account_info(),
-            payer: &self.player.to_account_info(),
-            system_program: &self.system_program.to_account_info(),
-            sysvar_instructions: sysvar_instructions,
-            spl_token_program: Some(token_program),
-            authorization_rules_program: None,
-            authorization_rules: None,
-        };
-
-        RevokeStandardV1Cpi::new(&self.token_metadata_program.to_account_info(), cpi_accounts)
+        UnlockV1Cpi::new(token_metadata_program, cpi_accounts, cpi_args)
             .invoke_signed(signers_seeds)?;
 
+        match self.config.pet_standard {
+            PetStandard::ProgrammableNonFungible => {
+                let cpi_accounts = RevokeStakingV1CpiAccounts {
+                    delegate_record: None,
+                    delegate: &self.config.to_account_info(),
+                    metadata,
+                    master_edition: Some(master_edition),
+                    token_record: token_record.as_ref(),
+                    mint: &self.nft_mint.to_account_info(),
+                    token: player_ata,
+                    authority: player,
+                    payer: player,
+                    system_program: &self.system_program.to_account_info(),
+                    sysvar_instructions,
+                    spl_token_program: Some(token_program),
+                    authorization_rules_program: authorization_rules_program.as_ref(),
+                    authorization_rules: authorization_rules.as_ref(),
+                };
+
+                RevokeStakingV1Cpi::new(token_metadata_program, cpi_accounts)
+                    .invoke_signed(signers_seeds)?;
+            }
+            _ => {
+                // Revoke the delegate standard v1
+                let cpi_accounts = RevokeStandardV1CpiAccounts {
+                    delegate_record: None,
+                    delegate: &self.config.to_account_info(),
+                    metadata,
+                    master_edition: Some(master_edition),
+                    token_record: None,
+                    mint: &self.nft_mint.to_account_info(),
+                    token: player_ata,
+                    authority: player,
+                    payer: player,
+                    system_program: &self.system_program.to_account_info(),
+                    sysvar_instructions,
+                    spl_token_program: Some(token_program),
+                    authorization_rules_program: None,
+                    authorization_rules: None,
+                };
+
+                RevokeStandardV1Cpi::new(token_metadata_program, cpi_accounts)
+                    .invoke_signed(signers_seeds)?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -17,18 +17,98 @@ declare_id!("2fiEN7yCZn3tQ3zVJ2iKTv7Tw5qkWU7gPsccZDvTZXQk");
 pub mod solapet_capstone {
     use super::*;
 
-    pub fn initialize(ctx: Context<InitializeGameConfig>, fees: u8) -> Result<()> {
-        ctx.accounts.init_game_config(&ctx.bumps, fees)?;
+    pub fn initialize(
+        ctx: Context<InitializeGameConfig>,
+        fee_bps: u16,
+        pet_standard: PetStandard,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_game_config(&ctx.bumps, fee_bps, pet_standard)?;
+        Ok(())
+    }
+
+    pub fn update_fees(ctx: Context<UpdateFees>, fee_bps: u16) -> Result<()> {
+        ctx.accounts.update_fees(fee_bps)?;
+        Ok(())
+    }
+
+    pub fn update_randomness_authority(
+        ctx: Context<UpdateRandomnessAuthority>,
+        authority: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.update_randomness_authority(authority)?;
+        Ok(())
+    }
+
+    pub fn update_turn_timeout(ctx: Context<UpdateTurnTimeout>, turn_timeout: i64) -> Result<()> {
+        ctx.accounts.update_turn_timeout(turn_timeout)?;
+        Ok(())
+    }
+
+    pub fn update_challenge_expiry(
+        ctx: Context<UpdateChallengeExpiry>,
+        challenge_expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.update_challenge_expiry(challenge_expiry)?;
+        Ok(())
+    }
+
+    pub fn update_base_uri(ctx: Context<UpdateBaseUri>, base_uri: String) -> Result<()> {
+        ctx.accounts.update_base_uri(base_uri)?;
+        Ok(())
+    }
+
+    pub fn update_mint_settings(
+        ctx: Context<UpdateMintSettings>,
+        mint_settings: MintSettings,
+    ) -> Result<()> {
+        ctx.accounts.update_mint_settings(mint_settings)?;
+        Ok(())
+    }
+
+    pub fn update_presale(ctx: Context<UpdatePresale>, presale: PresaleSettings) -> Result<()> {
+        ctx.accounts.update_presale(presale)?;
+        Ok(())
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_fees(amount)?;
+        Ok(())
+    }
+
+    pub fn update_bet_mint(ctx: Context<UpdateBetMint>, enabled: bool) -> Result<()> {
+        ctx.accounts.update_bet_mint(enabled)?;
+        Ok(())
+    }
+
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.propose_admin(new_admin)?;
         Ok(())
     }
 
-    pub fn update_fees(ctx: Context<UpdateFees>, fees: u8) -> Result<()> {
-        ctx.accounts.update_fees(fees)?;
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        ctx.accounts.accept_admin()?;
         Ok(())
     }
 
-    pub fn mint_pet(ctx: Context<MintPetNft>, uri: String) -> Result<()> {
-        ctx.accounts.mint_pet_nft(uri)?;
+    pub fn update_role(ctx: Context<UpdateRole>, role: AdminRole, authority: Pubkey) -> Result<()> {
+        ctx.accounts.update_role(role, authority)?;
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.set_paused(paused)?;
+        Ok(())
+    }
+
+    pub fn mint_pet(
+        ctx: Context<MintPetNft>,
+        proof: Option<Vec<[u8; 32]>>,
+        randomness: Vec<u8>,
+    ) -> Result<()> {
+        let price = ctx.accounts.record_mint(&ctx.bumps, proof)?;
+        ctx.accounts.mint_pet_nft(&ctx.bumps, &randomness)?;
+        ctx.accounts.pay_mint_price(price)?;
         Ok(())
     }
 
@@ -51,25 +131,68 @@ pub mod solapet_capstone {
         Ok(())
     }
 
-    pub fn init_pet_duel(ctx: Context<InitPetDuel>, bet_amount: u64) -> Result<()> {
-        ctx.accounts.initilize(&ctx.bumps, bet_amount)?;
+    pub fn refresh_pet_metadata(ctx: Context<RefreshPetMetadata>) -> Result<()> {
+        ctx.accounts.refresh_metadata()?;
+        Ok(())
+    }
+
+    pub fn migrate_pet_stats(ctx: Context<MigratePetStats>) -> Result<()> {
+        ctx.accounts.migrate(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn init_pet_duel(
+        ctx: Context<InitPetDuel>,
+        bet_amount: u64,
+        randomness_mode: RandomnessMode,
+        commitment: [u8; 32],
+        opponent: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.initilize(
+            &ctx.bumps,
+            bet_amount,
+            randomness_mode,
+            commitment,
+            opponent,
+        )?;
         if bet_amount > 0 {
             ctx.accounts.deposite(bet_amount)?;
         }
         Ok(())
     }
 
-    pub fn accept_pet_duel(ctx: Context<AcceptPetDuel>) -> Result<()> {
-        ctx.accounts.accept_duel()?;
+    pub fn accept_pet_duel(ctx: Context<AcceptPetDuel>, commitment: [u8; 32]) -> Result<()> {
+        ctx.accounts.accept_duel(commitment)?;
         if ctx.accounts.pet_duel_account.bet_amount > 0 {
             ctx.accounts.deposite()?;
         }
         Ok(())
     }
 
-    pub fn pet_attack(ctx: Context<PetAttack>, sig: Vec<u8>) -> Result<()> {
-        // verify_ed25519_signature(&ctx.accounts.instructions_sysvar.to_account_info(), &sig)?;
-        ctx.accounts.attack(&sig)?;
+    pub fn cancel_pet_duel(ctx: Context<CancelPetDuel>) -> Result<()> {
+        ctx.accounts.cancel()?;
+        if ctx.accounts.pet_duel_account.bet_amount > 0 {
+            ctx.accounts.refund()?;
+        }
+        Ok(())
+    }
+
+    pub fn pet_attack(
+        ctx: Context<PetAttack>,
+        randomness: Vec<u8>,
+        action: DuelAction,
+    ) -> Result<()> {
+        ctx.accounts.attack(&randomness, action)?;
+        Ok(())
+    }
+
+    pub fn claim_forfeit(ctx: Context<ClaimForfeit>) -> Result<()> {
+        ctx.accounts.claim_forfeit()?;
+        Ok(())
+    }
+
+    pub fn surrender_pet_duel(ctx: Context<SurrenderPetDuel>) -> Result<()> {
+        ctx.accounts.surrender()?;
         Ok(())
     }
 
@@ -79,6 +202,7 @@ pub mod solapet_capstone {
                 && ctx.accounts.winner.key() == ctx.accounts.pet_duel_account.winner.unwrap(),
             error::ErrorCode::UnauthorizedAction
         );
+        ctx.accounts.release_winner_pet()?;
         if ctx.accounts.pet_duel_account.bet_amount > 0 {
             msg!("bet amount greater than zero, claming");
             ctx.accounts.claim()?;
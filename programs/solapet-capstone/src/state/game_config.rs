@@ -1,22 +1,412 @@
 use anchor_lang::prelude::*;
+use mpl_token_metadata::types::TokenStandard;
 
-use crate::ANCHOR_DISCRIMINATOR;
+use crate::{
+    error::ErrorCode, Mood, ANCHOR_DISCRIMINATOR, FEE_CHANGE_DELAY, MAX_BASE_URI_LENGTH,
+    MAX_BET_MINTS, MAX_FEE_BPS,
+};
+
+/// Token standard used by every pet of the configured collection.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PetStandard {
+    NonFungible,
+    /// Locked through a staking delegate; needs token record accounts and honours
+    /// the collection's rule set.
+    ProgrammableNonFungible,
+    /// Metaplex Core asset, locked through a freeze delegate plugin owned by the
+    /// `game_config` PDA.
+    Core,
+}
+
+impl PetStandard {
+    /// Token Metadata standard of the pets, or `None` for Core assets.
+    pub fn token_standard(&self) -> Option<TokenStandard> {
+        match self {
+            PetStandard::NonFungible => Some(TokenStandard::NonFungible),
+            PetStandard::ProgrammableNonFungible => Some(TokenStandard::ProgrammableNonFungible),
+            PetStandard::Core => None,
+        }
+    }
+}
+
+/// Public mint rules, set by the admin through `update_mint_settings`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct MintSettings {
+    /// Lamports paid into the game vault for every pet.
+    pub price: u64,
+    /// Total number of pets that can ever be minted.
+    pub max_supply: u64,
+    /// Pets a single wallet may mint.
+    pub max_per_wallet: u32,
+    /// Minting is open while `start <= now < end`.
+    pub start: i64,
+    pub end: i64,
+}
+
+impl MintSettings {
+    pub const INIT_SPACE: usize = 8 + 8 + 4 + 8 + 8;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(self.start < self.end, ErrorCode::InvalidMintSettings);
+        require!(
+            self.max_supply > 0 && self.max_per_wallet > 0,
+            ErrorCode::InvalidMintSettings
+        );
+        Ok(())
+    }
+
+    pub fn require_open(&self, now: i64) -> Result<()> {
+        require!(self.start <= now && now < self.end, ErrorCode::MintNotOpen);
+        Ok(())
+    }
+}
+
+/// Allowlist phase, set by the admin through `update_presale`. Wallets prove
+/// membership with a Merkle proof of `hash(player)` against `merkle_root`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PresaleSettings {
+    pub merkle_root: [u8; 32],
+    /// Lamports paid into the game vault for every presale pet.
+    pub price: u64,
+    /// Presale pets a single allowlisted wallet may mint.
+    pub max_per_wallet: u32,
+    /// The presale runs while `start <= now < end`.
+    pub start: i64,
+    pub end: i64,
+}
+
+impl PresaleSettings {
+    pub const INIT_SPACE: usize = 32 + 8 + 4 + 8 + 8;
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.start < self.end && self.max_per_wallet > 0,
+            ErrorCode::InvalidPresaleSettings
+        );
+        Ok(())
+    }
+
+    pub fn require_active(&self, now: i64) -> Result<()> {
+        require!(
+            self.start <= now && now < self.end,
+            ErrorCode::PresaleNotActive
+        );
+        Ok(())
+    }
+}
+
+/// An SPL token accepted for duel bets, with its own fee and escrow accounting.
+/// Entries are never removed so their balances stay accounted for.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct BetMint {
+    pub mint: Pubkey,
+    /// Only enabled mints can be used for new duels; open duels still settle.
+    pub enabled: bool,
+    pub accrued_fees: u64,
+    pub total_escrow: u64,
+}
+
+impl BetMint {
+    pub const INIT_SPACE: usize = 32 + 1 + 8 + 8;
+}
+
+/// Free, unlimited and always open until the admin says otherwise.
+impl Default for MintSettings {
+    fn default() -> Self {
+        Self {
+            price: 0,
+            max_supply: u64::MAX,
+            max_per_wallet: u32::MAX,
+            start: 0,
+            end: i64::MAX,
+        }
+    }
+}
+
+/// Keys the admin can delegate part of its powers to.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AdminRole {
+    /// Sets and withdraws fees.
+    FeeManager,
+    /// Pauses and unpauses minting, deposits and new duels.
+    Pauser,
+    /// Updates the pets' metadata base URI.
+    MetadataUpdater,
+    /// Signs pet genomes and oracle duel turns.
+    RandomnessAuthority,
+}
 
 #[account]
 pub struct GameConfig {
     pub admin: Pubkey,
+    /// Key proposed by the admin to take over; becomes `admin` once it accepts.
+    pub pending_admin: Option<Pubkey>,
+    pub fee_manager: Pubkey,
+    pub pauser: Pubkey,
+    pub metadata_updater: Pubkey,
+    /// Set by the pauser; blocks minting, deposits and new duels.
+    pub paused: bool,
     pub collection_mint: Pubkey,
     pub game_vault: Pubkey,
-    pub fees: u8,
+    /// Duel fee in basis points of the pot, at most `MAX_FEE_BPS`.
+    pub fee_bps: u16,
+    /// Fee set by `update_fees`, replacing `fee_bps` from `fee_effective_after` on.
+    pub pending_fee_bps: Option<u16>,
+    pub fee_effective_after: i64,
+    pub randomness_authority: Option<Pubkey>,
+    pub turn_timeout: i64,
+    pub challenge_expiry: i64,
+    pub pets_minted: u64,
+    pub base_uri: String,
+    pub mint_settings: MintSettings,
+    pub presale: PresaleSettings,
+    pub pet_standard: PetStandard,
+    /// Owner of the collection account: SPL Token or Token-2022 for Token Metadata
+    /// pets, Metaplex Core for Core pets.
+    pub token_program: Pubkey,
+    /// House share of the vault: duel fees and mint proceeds not yet withdrawn.
+    pub accrued_fees: u64,
+    /// Bets held in the vault for duels that have not been paid out or refunded.
+    pub total_escrow: u64,
+    /// SPL tokens accepted for bets besides SOL.
+    pub bet_mints: Vec<BetMint>,
     pub bump: u8,
     pub vault_bump: u8,
 }
 
 impl GameConfig {
-    pub const INIT_SPACE: usize = ANCHOR_DISCRIMINATOR + 32 + 32 + 32 + 1 + 1 + 1;
+    pub const INIT_SPACE: usize = ANCHOR_DISCRIMINATOR
+        + 32 // admin
+        + 33 // pending_admin
+        + 32 // fee_manager
+        + 32 // pauser
+        + 32 // metadata_updater
+        + 1 // paused
+        + 32 // collection_mint
+        + 32 // game_vault
+        + 2 // fee_bps
+        + 3 // pending_fee_bps
+        + 8 // fee_effective_after
+        + 33 // randomness_authority
+        + 8 // turn_timeout
+        + 8 // challenge_expiry
+        + 8 // pets_minted
+        + (4 + MAX_BASE_URI_LENGTH) // base_uri
+        + MintSettings::INIT_SPACE // mint_settings
+        + PresaleSettings::INIT_SPACE // presale
+        + 1 // pet_standard
+        + 32 // token_program
+        + 8 // accrued_fees
+        + 8 // total_escrow
+        + (4 + MAX_BET_MINTS * BetMint::INIT_SPACE) // bet_mints
+        + 1 // bump
+        + 1; // vault_bump
+
+    /// Starts handing the admin role over to `new_admin`, or cancels a pending
+    /// handover when `None`.
+    pub fn propose_admin(&mut self, new_admin: Option<Pubkey>) -> Result<()> {
+        self.pending_admin = new_admin;
+        Ok(())
+    }
+
+    pub fn accept_admin(&mut self, new_admin: Pubkey) -> Result<()> {
+        require!(
+            self.pending_admin == Some(new_admin),
+            ErrorCode::InvalidAdminAccess
+        );
+        self.admin = new_admin;
+        self.pending_admin = None;
+        Ok(())
+    }
+
+    pub fn update_role(&mut self, role: AdminRole, authority: Pubkey) -> Result<()> {
+        match role {
+            AdminRole::FeeManager => self.fee_manager = authority,
+            AdminRole::Pauser => self.pauser = authority,
+            AdminRole::MetadataUpdater => self.metadata_updater = authority,
+            AdminRole::RandomnessAuthority => self.randomness_authority = Some(authority),
+        }
+        Ok(())
+    }
+
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        self.paused = paused;
+        Ok(())
+    }
+
+    /// Schedules `fee_bps` to take effect after `FEE_CHANGE_DELAY`, so it can never
+    /// apply to duels already created. Replaces any change still pending.
+    pub fn update_fees(&mut self, fee_bps: u16, now: i64) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ErrorCode::InvalidFeesPercentage);
+        self.fee_bps = self.current_fee_bps(now);
+        self.pending_fee_bps = Some(fee_bps);
+        self.fee_effective_after = now
+            .checked_add(FEE_CHANGE_DELAY)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Fee rate in effect at `now`, including a pending change whose delay has passed.
+    pub fn current_fee_bps(&self, now: i64) -> u16 {
+        match self.pending_fee_bps {
+            Some(fee_bps) if now >= self.fee_effective_after => fee_bps,
+            _ => self.fee_bps,
+        }
+    }
+
+    pub fn update_randomness_authority(&mut self, authority: Option<Pubkey>) -> Result<()> {
+        self.randomness_authority = authority;
+        Ok(())
+    }
+
+    pub fn update_turn_timeout(&mut self, turn_timeout: i64) -> Result<()> {
+        require!(turn_timeout > 0, ErrorCode::InvalidTurnTimeout);
+        self.turn_timeout = turn_timeout;
+        Ok(())
+    }
+
+    pub fn update_challenge_expiry(&mut self, challenge_expiry: i64) -> Result<()> {
+        require!(challenge_expiry > 0, ErrorCode::InvalidChallengeExpiry);
+        self.challenge_expiry = challenge_expiry;
+        Ok(())
+    }
+
+    pub fn update_base_uri(&mut self, base_uri: String) -> Result<()> {
+        require!(
+            base_uri.len() <= MAX_BASE_URI_LENGTH,
+            ErrorCode::InvalidBaseUri
+        );
+        self.base_uri = base_uri;
+        Ok(())
+    }
+
+    pub fn update_mint_settings(&mut self, mint_settings: MintSettings) -> Result<()> {
+        mint_settings.validate()?;
+        self.mint_settings = mint_settings;
+        Ok(())
+    }
+
+    pub fn update_presale(&mut self, presale: PresaleSettings) -> Result<()> {
+        presale.validate()?;
+        self.presale = presale;
+        Ok(())
+    }
 
-    pub fn update_fees(&mut self, updated_fees: u8) -> Result<()> {
-        self.fees = updated_fees;
+    /// Adds `mint` to the accepted bet mints, or enables or disables it if it is
+    /// already there.
+    pub fn set_bet_mint(&mut self, mint: Pubkey, enabled: bool) -> Result<()> {
+        if let Some(bet_mint) = self.bet_mints.iter_mut().find(|m| m.mint == mint) {
+            bet_mint.enabled = enabled;
+            return Ok(());
+        }
+        require!(
+            self.bet_mints.len() < MAX_BET_MINTS,
+            ErrorCode::TooManyBetMints
+        );
+        self.bet_mints.push(BetMint {
+            mint,
+            enabled,
+            accrued_fees: 0,
+            total_escrow: 0,
+        });
         Ok(())
     }
+
+    pub fn require_bet_mint_enabled(&self, mint: Pubkey) -> Result<()> {
+        require!(
+            self.bet_mints.iter().any(|m| m.mint == mint && m.enabled),
+            ErrorCode::BetMintNotAllowed
+        );
+        Ok(())
+    }
+
+    /// `(accrued_fees, total_escrow)` for SOL, or for `bet_mint` when given.
+    fn ledger(&mut self, bet_mint: Option<Pubkey>) -> Result<(&mut u64, &mut u64)> {
+        match bet_mint {
+            None => Ok((&mut self.accrued_fees, &mut self.total_escrow)),
+            Some(mint) => {
+                let bet_mint = self
+                    .bet_mints
+                    .iter_mut()
+                    .find(|m| m.mint == mint)
+                    .ok_or(ErrorCode::BetMintNotAllowed)?;
+                Ok((&mut bet_mint.accrued_fees, &mut bet_mint.total_escrow))
+            }
+        }
+    }
+
+    pub fn escrow_bet(&mut self, bet_mint: Option<Pubkey>, amount: u64) -> Result<()> {
+        let (_, total_escrow) = self.ledger(bet_mint)?;
+        *total_escrow = total_escrow
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    pub fn release_escrow(&mut self, bet_mint: Option<Pubkey>, amount: u64) -> Result<()> {
+        let (_, total_escrow) = self.ledger(bet_mint)?;
+        *total_escrow = total_escrow
+            .checked_sub(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Takes a settled duel's pot out of escrow; whatever is not paid to the
+    /// winner becomes house fees.
+    pub fn settle_pot(&mut self, bet_mint: Option<Pubkey>, pot: u64, payout: u64) -> Result<()> {
+        self.release_escrow(bet_mint, pot)?;
+        let fee = pot.checked_sub(payout).ok_or(ErrorCode::Overflow)?;
+        self.accrue_fees(bet_mint, fee)
+    }
+
+    pub fn accrue_fees(&mut self, bet_mint: Option<Pubkey>, amount: u64) -> Result<()> {
+        let (accrued_fees, _) = self.ledger(bet_mint)?;
+        *accrued_fees = accrued_fees
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Takes `amount` out of the accrued fees, refusing to leave the vault (or its
+    /// token account for `bet_mint`) with less than the bets it holds in escrow.
+    pub fn withdraw_fees(
+        &mut self,
+        bet_mint: Option<Pubkey>,
+        amount: u64,
+        vault_balance: u64,
+    ) -> Result<()> {
+        let (accrued_fees, total_escrow) = self.ledger(bet_mint)?;
+        require!(amount <= *accrued_fees, ErrorCode::InsufficientFees);
+        let remaining = vault_balance
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientFees)?;
+        require!(remaining >= *total_escrow, ErrorCode::InsufficientFees);
+        *accrued_fees -= amount;
+        Ok(())
+    }
+
+    /// Counts a newly minted pet and returns its number, starting from 1.
+    pub fn next_pet_number(&mut self) -> Result<u64> {
+        require!(
+            self.pets_minted < self.mint_settings.max_supply,
+            ErrorCode::MaxSupplyReached
+        );
+        self.pets_minted = self.pets_minted.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(self.pets_minted)
+    }
+
+    pub fn pet_name(number: u64) -> String {
+        format!("Pet #{}", number)
+    }
+
+    /// `{base_uri}{number}.json?level={level}&mood={mood}`
+    pub fn pet_uri(&self, number: u64, level: u8, mood: Mood) -> String {
+        format!(
+            "{}{}.json?level={}&mood={}",
+            self.base_uri,
+            number,
+            level,
+            mood.as_str()
+        )
+    }
 }
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, ANCHOR_DISCRIMINATOR};
+
+/// Per-wallet mint counter, keyed by `[b"mint_record", player]`. The total count
+/// also seeds each new pet's mint address.
+#[account]
+pub struct MintRecord {
+    pub player: Pubkey,
+    /// Pets minted by the wallet in any phase.
+    pub minted: u32,
+    /// Of those, pets minted during the presale.
+    pub presale_minted: u32,
+    pub bump: u8,
+}
+
+impl MintRecord {
+    pub const INIT_SPACE: usize = ANCHOR_DISCRIMINATOR + 32 + 4 + 4 + 1;
+
+    /// Public mints are limited separately from the presale allowance.
+    pub fn record_mint(&mut self, max_per_wallet: u32) -> Result<()> {
+        require!(
+            self.minted - self.presale_minted < max_per_wallet,
+            ErrorCode::WalletMintLimitReached
+        );
+        self.minted = self.minted.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    pub fn record_presale_mint(&mut self, allowance: u32) -> Result<()> {
+        require!(
+            self.presale_minted < allowance,
+            ErrorCode::PresaleMintLimitReached
+        );
+        self.presale_minted += 1;
+        self.minted = self.minted.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+}
@@ -6,3 +6,9 @@ pub use pet_stats::*;
 
 pub mod pet_duel;
 pub use pet_duel::*;
+
+pub mod pet_genome;
+pub use pet_genome::*;
+
+pub mod mint_record;
+pub use mint_record::*;
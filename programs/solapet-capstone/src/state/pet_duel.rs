@@ -1,7 +1,13 @@
 // state/pet_duel.rs
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::hash::{hash, hashv},
+};
 
-use crate::{error::ErrorCode, gen_number, ANCHOR_DISCRIMINATOR};
+use crate::{
+    error::ErrorCode, gen_number, DuelAction, PetStats, ANCHOR_DISCRIMINATOR, HEAL_COOLDOWN_TURNS,
+    SPECIAL_COOLDOWN_TURNS,
+};
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum DuelStatus {
@@ -11,16 +17,119 @@ pub enum DuelStatus {
     InProgress,
 }
 
+/// Where the per-turn damage seed comes from, fixed for the lifetime of a duel.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum RandomnessMode {
+    /// Each player commits to the head of a hash chain and reveals one preimage per turn.
+    CommitReveal,
+    /// The configured randomness authority signs `(duel, turn, attacker)` for every turn.
+    Oracle,
+}
+
+/// Combat attributes snapshotted from a pet's care stats when it enters a duel.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CombatStats {
+    /// 70 for a starving pet up to 100 for a well-fed one, plus one per level gained.
+    pub max_health: u8,
+    /// Flat damage added to every hit: up to 10 for a fully rested pet, plus one
+    /// for every five levels.
+    pub attack_bonus: u8,
+    /// Percent chance of a double-damage hit, up to 20 for a clean pet.
+    pub crit_chance: u8,
+}
+
+/// Care stats a pet actually spent to enter a duel; hunger and hygiene stop at zero.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DuelCost {
+    pub energy: u8,
+    pub hunger: u8,
+    pub hygiene: u8,
+}
+
+impl DuelCost {
+    pub const INIT_SPACE: usize = 1 + 1 + 1;
+}
+
+impl CombatStats {
+    pub const INIT_SPACE: usize = 1 + 1 + 1;
+
+    pub fn from_pet_stats(stats: &PetStats) -> Self {
+        let level_bonus = stats.level.saturating_sub(1);
+        Self {
+            max_health: 70 + (stats.hunger as u16 * 30 / 100) as u8 + level_bonus,
+            attack_bonus: stats.energy / 10 + stats.level / 5,
+            crit_chance: stats.hygiene / 5,
+        }
+    }
+
+    pub fn roll_damage(&self, seed: &[u8]) -> Result<u8> {
+        let damage = gen_number(seed, 30)?.saturating_add(self.attack_bonus);
+
+        let crit_roll = gen_number(&hashv(&[seed, b"crit"]).to_bytes(), 100)?;
+        if crit_roll <= self.crit_chance {
+            return Ok(damage.saturating_mul(2));
+        }
+        Ok(damage)
+    }
+}
+
+/// Per-duelist action state that carries over between turns.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DuelistState {
+    /// Set by a guard; halves the next hit taken before the duelist's next turn.
+    pub guarding: bool,
+    /// Own turns left before the special can be used again.
+    pub special_cooldown: u8,
+    /// Own turns left before the pet can heal again.
+    pub heal_cooldown: u8,
+}
+
+impl DuelistState {
+    pub const INIT_SPACE: usize = 1 + 1 + 1;
+
+    /// Checks `action` is off cooldown before ticking, so a cooldown of N keeps the
+    /// action unavailable for the duelist's next N turns.
+    fn begin_turn(&mut self, action: &DuelAction) -> Result<()> {
+        let cooldown = match action {
+            DuelAction::Special => self.special_cooldown,
+            DuelAction::Heal => self.heal_cooldown,
+            DuelAction::Attack | DuelAction::Guard => 0,
+        };
+        require!(cooldown == 0, ErrorCode::ActionOnCooldown);
+
+        self.guarding = false;
+        self.special_cooldown = self.special_cooldown.saturating_sub(1);
+        self.heal_cooldown = self.heal_cooldown.saturating_sub(1);
+        Ok(())
+    }
+}
+
 #[account]
 pub struct PetDuel {
     pub challenger: Pubkey,
     pub defender: Pubkey,
     pub winner: Option<Pubkey>,
 
+    /// NFT mints of the pets fighting; their `PetStats` are derived from these.
+    pub challenger_pet: Pubkey,
+    pub defender_pet: Pubkey,
+
     pub challenger_pet_health: u8,
     pub defender_pet_health: u8,
 
+    pub challenger_combat: CombatStats,
+    pub defender_combat: CombatStats,
+
+    pub challenger_state: DuelistState,
+    pub defender_state: DuelistState,
+
     pub bet_amount: u64,
+    /// SPL token the bets are placed in, or `None` for SOL.
+    pub bet_mint: Option<Pubkey>,
+    /// Fee rate in basis points when the duel was created; the pot is settled at it.
+    pub fee_bps: u16,
+    /// What entering the duel cost the challenger's pet, given back if it is cancelled.
+    pub challenger_cost: DuelCost,
 
     pub duel_status: DuelStatus,
 
@@ -28,19 +137,66 @@ pub struct PetDuel {
 
     pub last_turn_timestamp: i64,
 
+    pub randomness_mode: RandomnessMode,
+    pub challenger_commitment: [u8; 32],
+    pub defender_commitment: [u8; 32],
+    pub turn: u32,
+
+    pub nonce: u64,
     pub bump: u8,
 }
 
 impl PetDuel {
-    pub const INIT_SPACE: usize = ANCHOR_DISCRIMINATOR + 32 + 32 + 33 + 1 + 1 + 8 + 1 + 1 + 8 + 1;
+    pub const INIT_SPACE: usize = ANCHOR_DISCRIMINATOR
+        + 32 // challenger
+        + 32 // defender
+        + 33 // winner
+        + 32 // challenger_pet
+        + 32 // defender_pet
+        + 1 // challenger_pet_health
+        + 1 // defender_pet_health
+        + CombatStats::INIT_SPACE // challenger_combat
+        + CombatStats::INIT_SPACE // defender_combat
+        + DuelistState::INIT_SPACE // challenger_state
+        + DuelistState::INIT_SPACE // defender_state
+        + 8 // bet_amount
+        + 33 // bet_mint
+        + 2 // fee_bps
+        + DuelCost::INIT_SPACE // challenger_cost
+        + 1 // duel_status
+        + 1 // challenger_turn
+        + 8 // last_turn_timestamp
+        + 1 // randomness_mode
+        + 32 // challenger_commitment
+        + 32 // defender_commitment
+        + 4 // turn
+        + 8 // nonce
+        + 1; // bump
 
-    pub fn accept_duel(&mut self, defender: Pubkey) -> Result<()> {
+    pub fn accept_duel(
+        &mut self,
+        defender: Pubkey,
+        defender_pet: Pubkey,
+        commitment: [u8; 32],
+        combat: CombatStats,
+    ) -> Result<()> {
         require!(
             self.duel_status == DuelStatus::Challenged,
             ErrorCode::DuelAlreadyStarted
         );
+        require_keys_neq!(self.challenger, defender, ErrorCode::CannotChallengeSelf);
+        // A targeted challenge is created with the opponent already in `defender`.
+        if self.defender != Pubkey::default() {
+            require_keys_eq!(self.defender, defender, ErrorCode::OnlyDefenderAction);
+        }
         self.defender = defender;
+        self.defender_pet = defender_pet;
+        self.defender_commitment = commitment;
+        self.defender_combat = combat;
+        self.defender_pet_health = combat.max_health;
         self.duel_status = DuelStatus::Started;
+        // The first turn's timeout runs from acceptance, not from the challenge.
+        self.last_turn_timestamp = Clock::get()?.unix_timestamp;
         Ok(())
     }
 
@@ -48,17 +204,185 @@ impl PetDuel {
         let now = Clock::get()?.unix_timestamp;
         self.last_turn_timestamp = now;
         self.challenger_turn = !self.challenger_turn;
+        self.turn = self.turn.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Only the challenger may withdraw a fresh challenge; once `challenge_expiry`
+    /// seconds have passed without a defender, anyone may crank the refund.
+    pub fn cancel_duel(&self, caller: Pubkey, challenge_expiry: i64) -> Result<()> {
+        require!(
+            self.duel_status == DuelStatus::Challenged,
+            ErrorCode::DuelAlreadyStarted
+        );
+
+        if caller != self.challenger {
+            let now = Clock::get()?.unix_timestamp;
+            require!(
+                now.saturating_sub(self.last_turn_timestamp) >= challenge_expiry,
+                ErrorCode::ChallengeNotExpired
+            );
+        }
         Ok(())
     }
 
-    pub fn perform_attack(&mut self, sig: &[u8]) -> Result<()> {
-        let damage: u8 = gen_number(&sig, 40)?;
+    pub fn require_started(&self) -> Result<()> {
+        match self.duel_status {
+            DuelStatus::Started => Ok(()),
+            DuelStatus::Finished => err!(ErrorCode::DuelFinished),
+            _ => err!(ErrorCode::DuelNotChallenged),
+        }
+    }
+
+    /// Ends a started duel in favour of the waiting player once the player whose
+    /// turn it is has let `turn_timeout` seconds pass without attacking.
+    pub fn claim_forfeit(&mut self, claimant: Pubkey, turn_timeout: i64) -> Result<()> {
+        self.require_started()?;
+
+        let waiting_player = if self.challenger_turn {
+            self.defender
+        } else {
+            self.challenger
+        };
+        require_keys_eq!(waiting_player, claimant, ErrorCode::UnauthorizedAction);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now.saturating_sub(self.last_turn_timestamp) >= turn_timeout,
+            ErrorCode::TurnTimeoutNotElapsed
+        );
+
+        self.duel_status = DuelStatus::Finished;
+        self.winner = Some(claimant);
+        Ok(())
+    }
 
+    /// Ends a started duel in favour of the opponent of `player`, who gives up so
+    /// their pet can leave the game.
+    pub fn surrender(&mut self, player: Pubkey) -> Result<()> {
+        self.require_started()?;
+
+        let opponent = if player == self.challenger {
+            self.defender
+        } else if player == self.defender {
+            self.challenger
+        } else {
+            return err!(ErrorCode::UnauthorizedAction);
+        };
+
+        self.duel_status = DuelStatus::Finished;
+        self.winner = Some(opponent);
+        Ok(())
+    }
+
+    /// Checks the attacker's reveal against their current commitment and advances
+    /// their hash chain. The returned seed mixes in the opponent's latest commitment,
+    /// so neither player can pick a favourable roll after committing.
+    pub fn reveal_seed(&mut self, reveal: &[u8]) -> Result<[u8; 32]> {
+        let reveal: [u8; 32] = reveal.try_into().map_err(|_| ErrorCode::InvalidReveal)?;
+
+        let (commitment, opponent_commitment) = if self.challenger_turn {
+            (&mut self.challenger_commitment, self.defender_commitment)
+        } else {
+            (&mut self.defender_commitment, self.challenger_commitment)
+        };
+
+        require!(
+            hash(&reveal).to_bytes() == *commitment,
+            ErrorCode::InvalidReveal
+        );
+        *commitment = reveal;
+
+        Ok(hashv(&[&reveal, &opponent_commitment, &self.turn.to_le_bytes()]).to_bytes())
+    }
+
+    /// Message the randomness authority signs for the current turn in oracle mode:
+    /// `duel || turn (u32 LE) || attacker`.
+    pub fn oracle_message(&self, duel: &Pubkey, attacker: &Pubkey) -> Vec<u8> {
+        [duel.as_ref(), &self.turn.to_le_bytes(), attacker.as_ref()].concat()
+    }
+
+    pub fn challenger_won(&self) -> bool {
+        self.winner == Some(self.challenger)
+    }
+
+    /// Mint of the winning pet, whose stats hold a duel slot until the pot is claimed.
+    pub fn winner_pet(&self) -> Pubkey {
+        if self.challenger_won() {
+            self.challenger_pet
+        } else {
+            self.defender_pet
+        }
+    }
+
+    /// Rejects `action` while it is on cooldown, then clears the acting duelist's
+    /// guard and ticks down their cooldowns.
+    pub fn begin_turn(&mut self, action: &DuelAction) -> Result<()> {
         if self.challenger_turn {
-            self.defender_pet_health = self.defender_pet_health.saturating_sub(damage);
+            self.challenger_state.begin_turn(action)
         } else {
-            self.challenger_pet_health = self.challenger_pet_health.saturating_sub(damage);
+            self.defender_state.begin_turn(action)
         }
+    }
+
+    pub fn perform_attack(&mut self, seed: &[u8]) -> Result<()> {
+        let damage = self.attacker_combat().roll_damage(seed)?;
+        self.hit_opponent(damage);
+        Ok(())
+    }
+
+    pub fn perform_special(&mut self, seed: &[u8]) -> Result<()> {
+        let damage = self.attacker_combat().roll_damage(seed)?;
+        self.attacker_state().special_cooldown = SPECIAL_COOLDOWN_TURNS;
+        self.hit_opponent(damage.saturating_add(damage / 2));
+        Ok(())
+    }
+
+    pub fn guard(&mut self) -> Result<()> {
+        self.attacker_state().guarding = true;
+        Ok(())
+    }
+
+    pub fn heal(&mut self, seed: &[u8]) -> Result<()> {
+        let amount = gen_number(seed, 20)?;
+        self.attacker_state().heal_cooldown = HEAL_COOLDOWN_TURNS;
+
+        let max_health = self.attacker_combat().max_health;
+        let health = if self.challenger_turn {
+            &mut self.challenger_pet_health
+        } else {
+            &mut self.defender_pet_health
+        };
+        *health = health.saturating_add(amount).min(max_health);
+        Ok(())
+    }
+
+    fn attacker_combat(&self) -> CombatStats {
+        if self.challenger_turn {
+            self.challenger_combat
+        } else {
+            self.defender_combat
+        }
+    }
+
+    fn attacker_state(&mut self) -> &mut DuelistState {
+        if self.challenger_turn {
+            &mut self.challenger_state
+        } else {
+            &mut self.defender_state
+        }
+    }
+
+    fn hit_opponent(&mut self, damage: u8) {
+        let (health, state) = if self.challenger_turn {
+            (&mut self.defender_pet_health, &mut self.defender_state)
+        } else {
+            (&mut self.challenger_pet_health, &mut self.challenger_state)
+        };
+
+        let damage = if state.guarding { damage / 2 } else { damage };
+        state.guarding = false;
+        *health = health.saturating_sub(damage);
 
         if self.challenger_pet_health == 0 || self.defender_pet_health == 0 {
             self.duel_status = DuelStatus::Finished;
@@ -68,6 +392,5 @@ impl PetDuel {
                 self.winner = Some(self.challenger);
             }
         }
-        Ok(())
     }
 }
@@ -0,0 +1,98 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+use crate::{gen_number, ANCHOR_DISCRIMINATOR};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Species {
+    Cat,
+    Dog,
+    Fox,
+    Bunny,
+    Dragon,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
+}
+
+impl Rarity {
+    /// Rolled out of 100: 5% legendary, 10% rare, 25% uncommon, 60% common.
+    fn from_roll(roll: u8) -> Self {
+        match roll {
+            1..=5 => Rarity::Legendary,
+            6..=15 => Rarity::Rare,
+            16..=40 => Rarity::Uncommon,
+            _ => Rarity::Common,
+        }
+    }
+
+    /// Added to every base stat.
+    fn stat_bonus(&self) -> u8 {
+        match self {
+            Rarity::Common => 0,
+            Rarity::Uncommon => 2,
+            Rarity::Rare => 4,
+            Rarity::Legendary => 6,
+        }
+    }
+}
+
+/// Traits rolled once when a pet is minted, keyed by `[b"genome", nft_mint]`.
+#[account]
+pub struct PetGenome {
+    pub nft_mint: Pubkey,
+    /// Mint order of the pet, used for its "Pet #N" name.
+    pub number: u64,
+
+    pub species: Species,
+    pub rarity: Rarity,
+
+    /// Base stats from 1 to 10, plus the rarity bonus.
+    pub vitality: u8,
+    pub strength: u8,
+    pub agility: u8,
+
+    pub bump: u8,
+}
+
+impl PetGenome {
+    pub const INIT_SPACE: usize = ANCHOR_DISCRIMINATOR + 32 + 8 + 1 + 1 + 1 + 1 + 1 + 1;
+
+    /// Message the randomness authority signs to seed a pet's traits:
+    /// `"genome" || nft_mint`. Ed25519 signatures are deterministic and the seed
+    /// uses nothing else, so the minter cannot reroll them.
+    pub fn oracle_message(nft_mint: &Pubkey) -> Vec<u8> {
+        [b"genome".as_ref(), nft_mint.as_ref()].concat()
+    }
+
+    pub fn generate(nft_mint: Pubkey, number: u64, seed: &[u8], bump: u8) -> Result<Self> {
+        let species = match gen_number(&hashv(&[seed, b"species"]).to_bytes(), 5)? {
+            1 => Species::Cat,
+            2 => Species::Dog,
+            3 => Species::Fox,
+            4 => Species::Bunny,
+            _ => Species::Dragon,
+        };
+        let rarity = Rarity::from_roll(gen_number(&hashv(&[seed, b"rarity"]).to_bytes(), 100)?);
+
+        let base_stat = |trait_name: &[u8]| -> Result<u8> {
+            let roll = gen_number(&hashv(&[seed, trait_name]).to_bytes(), 10)?;
+            Ok(roll + rarity.stat_bonus())
+        };
+
+        Ok(Self {
+            nft_mint,
+            number,
+            species,
+            rarity,
+            vitality: base_stat(b"vitality")?,
+            strength: base_stat(b"strength")?,
+            agility: base_stat(b"agility")?,
+            bump,
+        })
+    }
+}
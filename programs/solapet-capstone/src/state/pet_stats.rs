@@ -1,6 +1,29 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, Discriminator};
 
-use crate::{error::ErrorCode, ANCHOR_DISCRIMINATOR};
+use crate::{
+    error::ErrorCode, DuelCost, ANCHOR_DISCRIMINATOR, DUEL_ENERGY_COST, DUEL_HUNGER_COST,
+    DUEL_HYGIENE_COST, MAX_ACTIVE_DUELS, MAX_PET_LEVEL, XP_PER_LEVEL, XP_PER_LOSS, XP_PER_WIN,
+};
+
+/// How the pet is doing overall, shown in its metadata URI.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mood {
+    Happy,
+    Content,
+    Sad,
+    Miserable,
+}
+
+impl Mood {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Mood::Happy => "happy",
+            Mood::Content => "content",
+            Mood::Sad => "sad",
+            Mood::Miserable => "miserable",
+        }
+    }
+}
 
 #[account]
 pub struct PetStats {
@@ -12,12 +35,36 @@ pub struct PetStats {
     pub last_bathed_timestamp: i64,
     pub last_slept_timestamp: i64,
 
-    // pub nft_mint: Pubkey,
     pub bump: u8,
+
+    pub duel_nonce: u64,
+    pub active_duels: u8,
+    /// Energy has decayed up to this time. Kept apart from `last_slept_timestamp`,
+    /// which limits how often the pet can sleep.
+    pub last_energy_decay_timestamp: i64,
+
+    // Fields below were appended in layout version 1.
+    pub version: u8,
+    pub experience: u64,
+    pub level: u8,
+    pub wins: u32,
+    pub losses: u32,
+    pub win_streak: u16,
+
+    // Layout version 2 keys the account by `[b"stats", nft_mint]` so the stats
+    // follow the pet rather than the wallet that deposited it.
+    pub nft_mint: Pubkey,
+    /// Wallet the pet is currently deposited by; cleared when it is withdrawn.
+    pub owner: Pubkey,
 }
 
 impl PetStats {
-    pub const INIT_SPACE: usize = ANCHOR_DISCRIMINATOR + 1 + 1 + 1 + 8 + 8 + 8 + 1;
+    pub const VERSION: u8 = 2;
+
+    /// Size of the original wallet-keyed layout, which ended at `bump`.
+    pub const LEGACY_SPACE: usize = ANCHOR_DISCRIMINATOR + 1 + 1 + 1 + 8 + 8 + 8 + 1;
+
+    pub const INIT_SPACE: usize = Self::LEGACY_SPACE + 8 + 1 + 8 + 1 + 8 + 1 + 4 + 4 + 2 + 32 + 32;
 
     pub fn feed(&mut self) -> Result<()> {
         self.hunger = 100;
@@ -34,36 +81,56 @@ impl PetStats {
     pub fn sleep(&mut self) -> Result<()> {
         let current_timestamp = Clock::get()?.unix_timestamp;
 
-        let sleep_delta = ((current_timestamp - self.last_slept_timestamp) / 900) as u8;
-
-        require!(sleep_delta >= 30, ErrorCode::InsufficientPetEnergy);
+        require!(
+            self.rested_enough_to_sleep(current_timestamp),
+            ErrorCode::InsufficientPetEnergy
+        );
         self.energy = 100; // TODO: Consider implementing gradual energy restoration
         self.last_slept_timestamp = current_timestamp;
+        self.last_energy_decay_timestamp = current_timestamp;
 
         Ok(())
     }
 
+    /// A pet may sleep again 30 quarter-hours after it last woke. Compared in `i64`,
+    /// so a long-neglected pet can always sleep.
+    fn rested_enough_to_sleep(&self, now: i64) -> bool {
+        now - self.last_slept_timestamp >= 30 * 900
+    }
+
     pub fn update_pet_energy(&mut self) -> Result<u8> {
         let current_timestamp = Clock::get()?.unix_timestamp;
 
-        let energy_delta = ((current_timestamp - self.last_slept_timestamp) / 3600) as u8;
-        self.energy = self.energy.saturating_sub(energy_delta).clamp(0, 100);
+        self.energy = decay(
+            self.energy,
+            &mut self.last_energy_decay_timestamp,
+            current_timestamp,
+            3600,
+        );
         Ok(self.energy)
     }
 
     pub fn update_pet_hygiene(&mut self) -> Result<u8> {
         let current_timestamp = Clock::get()?.unix_timestamp;
 
-        let hygiene_delta = ((current_timestamp - self.last_bathed_timestamp) / 3600) as u8;
-        self.hygiene = self.hygiene.saturating_sub(hygiene_delta).clamp(0, 100);
+        self.hygiene = decay(
+            self.hygiene,
+            &mut self.last_bathed_timestamp,
+            current_timestamp,
+            3600,
+        );
         Ok(self.hygiene)
     }
 
     pub fn update_pet_hunger(&mut self) -> Result<u8> {
         let current_timestamp = Clock::get()?.unix_timestamp;
 
-        let hunger_delta = ((current_timestamp - self.last_fed_timestamp) / 1800) as u8;
-        self.hunger = self.hunger.saturating_sub(hunger_delta).clamp(0, 100);
+        self.hunger = decay(
+            self.hunger,
+            &mut self.last_fed_timestamp,
+            current_timestamp,
+            1800,
+        );
         Ok(self.hunger)
     }
 
@@ -73,4 +140,273 @@ impl PetStats {
         self.update_pet_hunger()?;
         Ok(())
     }
+    /// Mood from the average of hunger, hygiene and energy. Expects decay to have
+    /// been applied already.
+    pub fn mood(&self) -> Mood {
+        let average = (self.hunger as u16 + self.hygiene as u16 + self.energy as u16) / 3;
+        match average {
+            70.. => Mood::Happy,
+            40..=69 => Mood::Content,
+            15..=39 => Mood::Sad,
+            _ => Mood::Miserable,
+        }
+    }
+
+    /// Reserves the next duel nonce for a challenge issued by this pet.
+    pub fn start_duel(&mut self) -> Result<u64> {
+        let nonce = self.duel_nonce;
+        self.duel_nonce = self.duel_nonce.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        self.join_duel()?;
+        Ok(nonce)
+    }
+
+    pub fn join_duel(&mut self) -> Result<()> {
+        require!(
+            self.active_duels < MAX_ACTIVE_DUELS,
+            ErrorCode::TooManyActiveDuels
+        );
+        self.active_duels += 1;
+        Ok(())
+    }
+
+    pub fn end_duel(&mut self) -> Result<()> {
+        self.active_duels = self.active_duels.saturating_sub(1);
+        Ok(())
+    }
+
+    /// Charges the cost of entering a duel and returns what was actually taken.
+    /// Expects decay to have been applied already.
+    pub fn spend_duel_energy(&mut self) -> Result<DuelCost> {
+        require!(
+            self.energy >= DUEL_ENERGY_COST,
+            ErrorCode::InsufficientPetEnergy
+        );
+        let cost = DuelCost {
+            energy: DUEL_ENERGY_COST,
+            hunger: self.hunger.min(DUEL_HUNGER_COST),
+            hygiene: self.hygiene.min(DUEL_HYGIENE_COST),
+        };
+        self.energy -= cost.energy;
+        self.hunger -= cost.hunger;
+        self.hygiene -= cost.hygiene;
+        Ok(cost)
+    }
+
+    /// Gives back what a challenge cancelled before anyone accepted it cost.
+    pub fn refund_duel_energy(&mut self, cost: &DuelCost) -> Result<()> {
+        self.energy = self.energy.saturating_add(cost.energy).min(100);
+        self.hunger = self.hunger.saturating_add(cost.hunger).min(100);
+        self.hygiene = self.hygiene.saturating_add(cost.hygiene).min(100);
+        Ok(())
+    }
+
+    /// Applies the outcome: a beaten pet is left exhausted, and both pets earn
+    /// experience towards their next level. The loser's duel slot is released here;
+    /// the winner keeps it until the pot is claimed.
+    pub fn finish_duel(&mut self, won: bool) -> Result<()> {
+        if won {
+            self.wins = self.wins.saturating_add(1);
+            self.win_streak = self.win_streak.saturating_add(1);
+            self.gain_experience(XP_PER_WIN);
+        } else {
+            self.end_duel()?;
+            self.losses = self.losses.saturating_add(1);
+            self.win_streak = 0;
+            self.gain_experience(XP_PER_LOSS);
+            self.update_pet_energy()?;
+            self.energy = 0;
+        }
+        Ok(())
+    }
+
+    fn gain_experience(&mut self, amount: u64) {
+        self.experience = self.experience.saturating_add(amount);
+        let level = 1 + self.experience / XP_PER_LEVEL;
+        self.level = level.min(MAX_PET_LEVEL as u64) as u8;
+    }
+
+    /// Decodes an account in the original layout and upgrades it to `VERSION`,
+    /// bound to the deposited `nft_mint`. Pets from before progression start at level 1.
+    pub fn from_legacy(data: &[u8], nft_mint: Pubkey, owner: Pubkey, bump: u8) -> Result<Self> {
+        require!(
+            data.len() == Self::LEGACY_SPACE && data.starts_with(&Self::DISCRIMINATOR),
+            ErrorCode::InvalidAccountState
+        );
+        let legacy = LegacyPetStats::deserialize(&mut &data[ANCHOR_DISCRIMINATOR..])?;
+
+        Ok(Self {
+            hunger: legacy.hunger,
+            hygiene: legacy.hygiene,
+            energy: legacy.energy,
+            last_fed_timestamp: legacy.last_fed_timestamp,
+            last_bathed_timestamp: legacy.last_bathed_timestamp,
+            last_slept_timestamp: legacy.last_slept_timestamp,
+            bump,
+            duel_nonce: 0,
+            active_duels: 0,
+            // The original layout decayed energy from the last sleep without persisting it.
+            last_energy_decay_timestamp: legacy.last_slept_timestamp,
+            version: Self::VERSION,
+            experience: 0,
+            level: 1,
+            wins: 0,
+            losses: 0,
+            win_streak: 0,
+            nft_mint,
+            owner,
+        })
+    }
+
+    /// Records a new depositor. Stats are kept from any earlier deposit of the same pet.
+    pub fn deposit(&mut self, owner: Pubkey) -> Result<()> {
+        require_keys_eq!(
+            self.owner,
+            Pubkey::default(),
+            ErrorCode::NftAlreadyDeposited
+        );
+        self.owner = owner;
+        Ok(())
+    }
+
+    /// A pet can only leave once none of its duels is unfinished or unclaimed.
+    pub fn withdraw(&mut self) -> Result<()> {
+        require!(self.active_duels == 0, ErrorCode::PetInActiveDuel);
+        self.owner = Pubkey::default();
+        Ok(())
+    }
+}
+
+/// `PetStats` as first deployed, keyed by `[b"stats", player]`. Its bump belongs
+/// to that address and is not carried over.
+#[derive(AnchorDeserialize)]
+struct LegacyPetStats {
+    hunger: u8,
+    hygiene: u8,
+    energy: u8,
+    last_fed_timestamp: i64,
+    last_bathed_timestamp: i64,
+    last_slept_timestamp: i64,
+    _bump: u8,
+}
+
+/// Applies one point of decay per elapsed `period` and moves `last_timestamp`
+/// forward by the periods consumed, so decay is never counted twice once persisted.
+fn decay(value: u8, last_timestamp: &mut i64, now: i64, period: i64) -> u8 {
+    let periods = now.saturating_sub(*last_timestamp).max(0) / period;
+    *last_timestamp += periods * period;
+    value
+        .saturating_sub(periods.min(u8::MAX as i64) as u8)
+        .clamp(0, 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_account() -> Vec<u8> {
+        let mut data = PetStats::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(&[90, 80, 70]);
+        data.extend_from_slice(&1_000i64.to_le_bytes());
+        data.extend_from_slice(&2_000i64.to_le_bytes());
+        data.extend_from_slice(&3_000i64.to_le_bytes());
+        data.push(254);
+        data
+    }
+
+    #[test]
+    fn sleeping_is_allowed_after_any_long_gap() {
+        let stats = PetStats::from_legacy(
+            &legacy_account(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            7,
+        )
+        .unwrap();
+        let last_slept = stats.last_slept_timestamp;
+
+        assert!(!stats.rested_enough_to_sleep(last_slept + 30 * 900 - 1));
+        assert!(stats.rested_enough_to_sleep(last_slept + 30 * 900));
+        // 64 hours is 256 quarter-hours, which used to wrap to zero as a `u8`.
+        assert!(stats.rested_enough_to_sleep(last_slept + 64 * 3600));
+        assert!(stats.rested_enough_to_sleep(last_slept + 70 * 3600));
+        assert!(stats.rested_enough_to_sleep(last_slept + 365 * 24 * 3600));
+    }
+
+    #[test]
+    fn cancelling_refunds_only_what_the_duel_took() {
+        let mut stats = PetStats::from_legacy(
+            &legacy_account(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            7,
+        )
+        .unwrap();
+        stats.energy = 100;
+        stats.hunger = 4;
+        stats.hygiene = 0;
+
+        let cost = stats.spend_duel_energy().unwrap();
+        assert_eq!((stats.energy, stats.hunger, stats.hygiene), (80, 0, 0));
+
+        stats.refund_duel_energy(&cost).unwrap();
+        assert_eq!((stats.energy, stats.hunger, stats.hygiene), (100, 4, 0));
+    }
+
+    #[test]
+    fn legacy_layout_is_the_original_account_size() {
+        assert_eq!(legacy_account().len(), PetStats::LEGACY_SPACE);
+        assert_eq!(PetStats::LEGACY_SPACE, 36);
+    }
+
+    #[test]
+    fn migrates_the_original_layout() {
+        let nft_mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+
+        let stats = PetStats::from_legacy(&legacy_account(), nft_mint, owner, 7).unwrap();
+
+        assert_eq!((stats.hunger, stats.hygiene, stats.energy), (90, 80, 70));
+        assert_eq!(stats.last_fed_timestamp, 1_000);
+        assert_eq!(stats.last_bathed_timestamp, 2_000);
+        assert_eq!(stats.last_slept_timestamp, 3_000);
+        assert_eq!(stats.last_energy_decay_timestamp, 3_000);
+        assert_eq!(stats.bump, 7);
+        assert_eq!((stats.duel_nonce, stats.active_duels), (0, 0));
+        assert_eq!(stats.version, PetStats::VERSION);
+        assert_eq!((stats.level, stats.experience), (1, 0));
+        assert_eq!((stats.wins, stats.losses, stats.win_streak), (0, 0, 0));
+        assert_eq!(stats.nft_mint, nft_mint);
+        assert_eq!(stats.owner, owner);
+    }
+
+    #[test]
+    fn migrated_stats_fill_the_current_layout() {
+        let stats = PetStats::from_legacy(
+            &legacy_account(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            7,
+        )
+        .unwrap();
+
+        let mut data = Vec::new();
+        stats.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), PetStats::INIT_SPACE);
+    }
+
+    #[test]
+    fn rejects_accounts_not_in_the_original_layout() {
+        let mut longer = legacy_account();
+        longer.extend_from_slice(&[0; 9]);
+        let mut other_account = legacy_account();
+        other_account[0] ^= 1;
+        let shorter = legacy_account()[..30].to_vec();
+
+        for data in [longer, other_account, shorter] {
+            assert_eq!(
+                PetStats::from_legacy(&data, Pubkey::new_unique(), Pubkey::new_unique(), 7).err(),
+                Some(ErrorCode::InvalidAccountState.into())
+            );
+        }
+    }
 }
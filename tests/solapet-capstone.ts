@@ -2,13 +2,16 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { SolapetCapstone } from "../target/types/solapet_capstone";
 import { assert, expect } from "chai";
+import { createHash, randomBytes } from "crypto";
 
 import {
+  ComputeBudgetProgram,
   Ed25519Program,
   Keypair,
   LAMPORTS_PER_SOL,
   PublicKey,
   sendAndConfirmTransaction,
+  SystemProgram,
   SYSVAR_INSTRUCTIONS_PUBKEY,
   Transaction,
 } from "@solana/web3.js";
@@ -21,6 +24,10 @@ import {
   mplTokenMetadata,
   TokenStandard,
   transferV1,
+  delegateCollectionV1,
+  fetchMetadata,
+  findMetadataDelegateRecordPda,
+  MetadataDelegateRole,
   MPL_TOKEN_METADATA_PROGRAM_ID,
   findMasterEditionPda,
 } from "@metaplex-foundation/mpl-token-metadata";
@@ -31,13 +38,51 @@ import {
   percentAmount,
   publicKey,
   PublicKey as UmiPublickkey,
+  unwrapOption,
 } from "@metaplex-foundation/umi";
 import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  createInitializeMintInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createMint,
+  ExtensionType,
   getAccount,
+  getMintLen,
+  getOrCreateAssociatedTokenAccount,
   getAssociatedTokenAddress,
+  getAssociatedTokenAddressSync,
+  TOKEN_2022_PROGRAM_ID,
   TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 
+// Commit-reveal randomness: a player commits to the head of a sha256 hash chain
+// and reveals the previous link on each of their turns.
+class HashChain {
+  private links: Buffer[];
+
+  constructor(length = 64) {
+    this.links = [randomBytes(32)];
+    for (let i = 0; i < length; i++) {
+      this.links.push(
+        createHash("sha256").update(this.links[this.links.length - 1]).digest()
+      );
+    }
+  }
+
+  get commitment(): number[] {
+    return Array.from(this.links[this.links.length - 1]);
+  }
+
+  peek(): Buffer {
+    return this.links[this.links.length - 2];
+  }
+
+  reveal(): Buffer {
+    this.links.pop();
+    return this.links[this.links.length - 1];
+  }
+}
+
 describe("solapet-capstone", () => {
   // Configure the client to use the local cluster.
   anchor.setProvider(anchor.AnchorProvider.env());
@@ -51,17 +96,31 @@ describe("solapet-capstone", () => {
   let admin: Keypair = Keypair.generate();
   let player1: Keypair = Keypair.generate();
   let player2: Keypair = Keypair.generate();
+  let player3: Keypair = Keypair.generate();
+  const oracle: Keypair = Keypair.generate();
+  let betMint: PublicKey;
   let petStat1: PublicKey;
   let petStat2: PublicKey;
-  let petDuel: PublicKey; // player 1 challanger
+  let petDuel: PublicKey; // player 1's pet challenges, duel nonce 0
+  let pendingDuel: PublicKey; // player 1's pet challenges, duel nonce 1
+  let oracleDuel: PublicKey; // player 3's pet challenges, duel nonce 0
+  let forfeitDuel: PublicKey; // player 3's pet challenges, duel nonce 1
   let player1ATA: PublicKey;
   let player2ATA: PublicKey;
   let nftMintAddress1: PublicKey;
   let nftMintAddress2: PublicKey;
+  let nftMintAddress3: PublicKey; // player 3's pet
+  let nftMintAddress4: PublicKey; // player 2's presale pet
+  let petStat3: PublicKey;
+  let petStat4: PublicKey;
   let metadata1: UmiPublickkey;
   let metadata2: UmiPublickkey;
   let masterEdition1: UmiPublickkey;
   let masterEdition2: UmiPublickkey;
+  let collectionDelegateRecord: UmiPublickkey;
+  const mintPrice = LAMPORTS_PER_SOL / 100;
+  const player1Chain = new HashChain();
+  const player2Chain = new HashChain();
 
   // umi stuff
   const umi = createUmi(provider.connection.rpcEndpoint).use(
@@ -73,6 +132,104 @@ describe("solapet-capstone", () => {
 
   const collectionMint = generateSigner(umi);
 
+  const findPetDuel = (challengerPet: PublicKey, nonce: number): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("pet_duel"),
+        challengerPet.toBuffer(),
+        new anchor.BN(nonce).toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    )[0];
+
+  // Pets are seeded by the wallet's mint count, so each wallet can mint several.
+  const findNftMint = (player: PublicKey, minted: number): PublicKey =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("nft_mint"),
+        player.toBuffer(),
+        new PublicKey(collectionMint.publicKey).toBuffer(),
+        new anchor.BN(minted).toArrayLike(Buffer, "le", 4),
+      ],
+      program.programId
+    )[0];
+
+  const [collectionMetadata] = findMetadataPda(umi, {
+    mint: collectionMint.publicKey,
+  });
+  const [collectionMasterEdition] = findMasterEditionPda(umi, {
+    mint: collectionMint.publicKey,
+  });
+
+  // Accounts for minting a pet beyond each player's first one.
+  const extraMintAccounts = (player: PublicKey, nftMint: PublicKey) => ({
+    player,
+    collectionMint: collectionMint.publicKey,
+    gameConfig,
+    playerTokenAccount: getAssociatedTokenAddressSync(nftMint, player),
+    nftMint,
+    metadata: findMetadataPda(umi, { mint: publicKey(nftMint) })[0],
+    masterEdition: findMasterEditionPda(umi, { mint: publicKey(nftMint) })[0],
+    collectionMetadata,
+    collectionMasterEdition,
+    collectionDelegateRecord,
+    metadataProgramInfo: MPL_TOKEN_METADATA_PROGRAM_ID,
+    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  // Accounts for depositing a pet beyond the first two.
+  const depositAccounts = (player: PublicKey, nftMint: PublicKey) => ({
+    player,
+    collectionMint: collectionMint.publicKey,
+    nftMint,
+    playerAta: getAssociatedTokenAddressSync(nftMint, player),
+    masterEdition: findMasterEditionPda(umi, { mint: publicKey(nftMint) })[0],
+    config: gameConfig,
+    metadata: findMetadataPda(umi, { mint: publicKey(nftMint) })[0],
+    tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+    sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    petStats: PublicKey.findProgramAddressSync(
+      [Buffer.from("stats"), nftMint.toBuffer()],
+      program.programId
+    )[0],
+    tokenProgram: TOKEN_PROGRAM_ID,
+  });
+
+  const sleep = (ms: number) =>
+    new Promise((resolve) => setTimeout(resolve, ms));
+
+  // Genome randomness: `signer` signs `"genome" || nftMint` with an Ed25519 program
+  // instruction that precedes the mint.
+  const signGenome = (signer: Keypair, nftMint: PublicKey) => {
+    const instruction = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message: Buffer.concat([Buffer.from("genome"), nftMint.toBuffer()]),
+    });
+    return { instruction, signature: instruction.data.subarray(48, 112) };
+  };
+
+  // Oracle randomness: `signer` signs `duel || turn (u32 LE) || attacker` with an
+  // Ed25519 program instruction that precedes the attack.
+  const signTurn = (
+    signer: Keypair,
+    duel: PublicKey,
+    turn: number,
+    attacker: PublicKey
+  ) => {
+    const instruction = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: signer.secretKey,
+      message: Buffer.concat([
+        duel.toBuffer(),
+        new anchor.BN(turn).toArrayLike(Buffer, "le", 4),
+        attacker.toBuffer(),
+      ]),
+    });
+    // web3.js lays the data out as a 16-byte header, the public key, then the signature.
+    return { instruction, signature: instruction.data.subarray(48, 112) };
+  };
+
   before(async () => {
     await Promise.all([
       provider.connection.confirmTransaction(
@@ -96,6 +253,13 @@ describe("solapet-capstone", () => {
         ),
         "finalized"
       ),
+      provider.connection.confirmTransaction(
+        await provider.connection.requestAirdrop(
+          player3.publicKey,
+          2 * LAMPORTS_PER_SOL
+        ),
+        "finalized"
+      ),
     ]);
 
     [gameVault] = PublicKey.findProgramAddressSync(
@@ -108,38 +272,43 @@ describe("solapet-capstone", () => {
       program.programId
     );
 
+    [collectionDelegateRecord] = findMetadataDelegateRecordPda(umi, {
+      mint: collectionMint.publicKey,
+      delegateRole: MetadataDelegateRole.Collection,
+      updateAuthority: umi.identity.publicKey,
+      delegate: publicKey(gameConfig),
+    });
+
+
+    nftMintAddress1 = findNftMint(player1.publicKey, 0);
+    nftMintAddress2 = findNftMint(player2.publicKey, 0);
+    nftMintAddress3 = findNftMint(player3.publicKey, 0);
+    nftMintAddress4 = findNftMint(player2.publicKey, 1);
+
     [petStat1] = PublicKey.findProgramAddressSync(
-      [Buffer.from("stats"), player1.publicKey.toBuffer()],
+      [Buffer.from("stats"), nftMintAddress1.toBuffer()],
       program.programId
     );
 
     [petStat2] = PublicKey.findProgramAddressSync(
-      [Buffer.from("stats"), player2.publicKey.toBuffer()],
+      [Buffer.from("stats"), nftMintAddress2.toBuffer()],
       program.programId
     );
 
-    [petDuel] = PublicKey.findProgramAddressSync(
-      [Buffer.from("pet_duel"), player1.publicKey.toBuffer()],
+    [petStat3] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stats"), nftMintAddress3.toBuffer()],
       program.programId
     );
 
-    [nftMintAddress1] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("nft_mint"),
-        player1.publicKey.toBuffer(),
-        new PublicKey(collectionMint.publicKey).toBuffer(),
-      ],
+    [petStat4] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stats"), nftMintAddress4.toBuffer()],
       program.programId
     );
 
-    [nftMintAddress2] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from("nft_mint"),
-        player2.publicKey.toBuffer(),
-        new PublicKey(collectionMint.publicKey).toBuffer(),
-      ],
-      program.programId
-    );
+    petDuel = findPetDuel(nftMintAddress1, 0);
+    pendingDuel = findPetDuel(nftMintAddress1, 1);
+    oracleDuel = findPetDuel(nftMintAddress3, 0);
+    forfeitDuel = findPetDuel(nftMintAddress3, 1);
 
     player1ATA = await getAssociatedTokenAddress(
       nftMintAddress1,
@@ -195,7 +364,7 @@ describe("solapet-capstone", () => {
 
   it("Is initilized game config!", async () => {
     await program.methods
-      .initialize(1)
+      .initialize(100, { nonFungible: {} })
       .accounts({
         admin: admin.publicKey,
         collectionMint: collectionMint.publicKey,
@@ -210,15 +379,26 @@ describe("solapet-capstone", () => {
     );
     expect(onChainConfig.admin.toBase58()).to.equal(admin.publicKey.toBase58());
     expect(onChainConfig.gameVault.toBase58()).to.equal(gameVault.toBase58());
-    expect(onChainConfig.fees.toString()).to.equal("1");
+    expect(onChainConfig.feeBps).to.equal(100);
+    expect(onChainConfig.pendingFeeBps).to.be.null;
+    expect(onChainConfig.petStandard).to.deep.equal({ nonFungible: {} });
+    expect(onChainConfig.tokenProgram.toBase58()).to.equal(
+      TOKEN_PROGRAM_ID.toBase58()
+    );
+    // Every role starts out with the admin.
+    expect(onChainConfig.feeManager.toBase58()).to.equal(
+      admin.publicKey.toBase58()
+    );
+    expect(onChainConfig.pauser.toBase58()).to.equal(admin.publicKey.toBase58());
+    expect(onChainConfig.paused).to.be.false;
   });
 
   it("Should fail to update the the fees for non admin key", async () => {
     try {
       await program.methods
-        .updateFees(5)
+        .updateFees(500)
         .accountsPartial({
-          admin: player1.publicKey,
+          feeManager: player1.publicKey,
           gameConfig: gameConfig,
         })
         .signers([player1])
@@ -228,25 +408,125 @@ describe("solapet-capstone", () => {
     }
   });
 
+  it("Should not set fees above the maximum", async () => {
+    try {
+      await program.methods
+        .updateFees(1_001)
+        .accountsPartial({
+          feeManager: admin.publicKey,
+          gameConfig: gameConfig,
+        })
+        .signers([admin])
+        .rpc();
+      assert.fail("Expected update_fees to fail");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidFeesPercentage");
+    }
+  });
+
   it("Should update the the fees for Admin key", async () => {
     await program.methods
-      .updateFees(5)
+      .updateFees(500)
       .accountsPartial({
-        admin: admin.publicKey,
+        feeManager: admin.publicKey,
         gameConfig: gameConfig,
       })
       .signers([admin])
       .rpc();
 
     const updatedConfig = await program.account.gameConfig.fetch(gameConfig);
-    expect(updatedConfig.fees.toString()).to.equal("5");
+    // The new fee is only scheduled; duels keep using the current one for now.
+    expect(updatedConfig.feeBps).to.equal(100);
+    expect(updatedConfig.pendingFeeBps).to.equal(500);
+    expect(updatedConfig.feeEffectiveAfter.toNumber()).to.be.greaterThan(
+      Math.floor(Date.now() / 1000)
+    );
     expect(updatedConfig.admin.toBase58()).to.equal(admin.publicKey.toBase58());
   });
 
+  it("Should update the base URI for Admin key", async () => {
+    await program.methods
+      .updateBaseUri("https://solapet.example/pets/")
+      .accountsPartial({
+        metadataUpdater: admin.publicKey,
+        gameConfig: gameConfig,
+      })
+      .signers([admin])
+      .rpc();
+
+    const updatedConfig = await program.account.gameConfig.fetch(gameConfig);
+    expect(updatedConfig.baseUri).to.equal("https://solapet.example/pets/");
+  });
+
+  it("Should delegate collection authority to the game config", async () => {
+    await delegateCollectionV1(umi, {
+      mint: collectionMint.publicKey,
+      authority: umi.identity,
+      delegate: publicKey(gameConfig),
+      tokenStandard: TokenStandard.NonFungible,
+    }).sendAndConfirm(umi);
+  });
+
+  it("Should update the mint settings for Admin key", async () => {
+    await program.methods
+      .updateMintSettings({
+        price: new anchor.BN(mintPrice),
+        maxSupply: new anchor.BN(10),
+        maxPerWallet: 1,
+        start: new anchor.BN(0),
+        end: new anchor.BN(Math.floor(Date.now() / 1000) + 24 * 60 * 60),
+      })
+      .accountsPartial({
+        admin: admin.publicKey,
+        gameConfig: gameConfig,
+      })
+      .signers([admin])
+      .rpc();
+
+    const updatedConfig = await program.account.gameConfig.fetch(gameConfig);
+    expect(updatedConfig.mintSettings.maxPerWallet).to.equal(1);
+  });
+
+  it("Should set the randomness authority for genomes and oracle duels", async () => {
+    await program.methods
+      .updateRandomnessAuthority(oracle.publicKey)
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+
+    const config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.randomnessAuthority.toBase58()).to.equal(
+      oracle.publicKey.toBase58()
+    );
+  });
+
+  it("Should not mint a pet without the authority's genome signature", async () => {
+    const { instruction, signature } = signGenome(
+      Keypair.generate(),
+      nftMintAddress1
+    );
+
+    try {
+      await program.methods
+        .mintPet(null, signature)
+        .accountsPartial(extraMintAccounts(player1.publicKey, nftMintAddress1))
+        .preInstructions([instruction])
+        .signers([player1])
+        .rpc();
+      assert.fail("Only the randomness authority may seed a genome");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidSigner");
+    }
+  });
+
   it("should mint a new nft pet for players", async () => {
+    const vaultBalanceBefore = await provider.connection.getBalance(gameVault);
+    const genome1 = signGenome(oracle, nftMintAddress1);
+    const genome2 = signGenome(oracle, nftMintAddress2);
+
     await Promise.all([
       program.methods
-        .mintPet("") // Empty URI string
+        .mintPet(null, genome1.signature)
         .accountsPartial({
           player: player1.publicKey,
           collectionMint: collectionMint.publicKey,
@@ -255,15 +535,20 @@ describe("solapet-capstone", () => {
           nftMint: nftMintAddress1,
           metadata: metadata1,
           masterEdition: masterEdition1,
+          collectionMetadata,
+          collectionMasterEdition,
+          collectionDelegateRecord,
           metadataProgramInfo: MPL_TOKEN_METADATA_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
+        .preInstructions([genome1.instruction])
         .signers([player1])
         .rpc(),
 
       program.methods
-        .mintPet("") // Empty URI string
+        .mintPet(null, genome2.signature)
         .accountsPartial({
           player: player2.publicKey,
           collectionMint: collectionMint.publicKey,
@@ -272,10 +557,15 @@ describe("solapet-capstone", () => {
           nftMint: nftMintAddress2,
           metadata: metadata2,
           masterEdition: masterEdition2,
+          collectionMetadata,
+          collectionMasterEdition,
+          collectionDelegateRecord,
           metadataProgramInfo: MPL_TOKEN_METADATA_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
+        .preInstructions([genome2.instruction])
         .signers([player2])
         .rpc(),
     ]);
@@ -323,6 +613,132 @@ describe("solapet-capstone", () => {
     expect(player2TokenAccount.owner.toString()).to.equal(
       player2.publicKey.toString()
     );
+
+    // Both pets are verified members of the collection.
+    for (const metadata of [metadata1, metadata2]) {
+      const collection = unwrapOption(
+        (await fetchMetadata(umi, metadata)).collection
+      );
+      expect(collection.key.toString()).to.equal(
+        collectionMint.publicKey.toString()
+      );
+      expect(collection.verified).to.equal(true);
+    }
+
+    // Each pet gets a genome and a name from its mint order.
+    const numbers = [];
+    for (const [nftMint, metadata] of [
+      [nftMintAddress1, metadata1],
+      [nftMintAddress2, metadata2],
+    ] as const) {
+      const [petGenome] = PublicKey.findProgramAddressSync(
+        [Buffer.from("genome"), nftMint.toBuffer()],
+        program.programId
+      );
+      const genome = await program.account.petGenome.fetch(petGenome);
+      const onChainMetadata = await fetchMetadata(umi, metadata);
+
+      expect(genome.nftMint.toBase58()).to.equal(nftMint.toBase58());
+      expect(genome.vitality).to.be.within(1, 16);
+      expect(onChainMetadata.name).to.equal(`Pet #${genome.number}`);
+      expect(onChainMetadata.uri).to.equal(
+        `https://solapet.example/pets/${genome.number}.json?level=1&mood=happy`
+      );
+      numbers.push(genome.number.toNumber());
+    }
+    expect(numbers.sort()).to.deep.equal([1, 2]);
+
+    const config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.petsMinted.toString()).to.equal("2");
+
+    // Both players paid the mint price into the vault.
+    const vaultBalanceAfter = await provider.connection.getBalance(gameVault);
+    expect(vaultBalanceAfter - vaultBalanceBefore).to.equal(2 * mintPrice);
+  });
+
+  it("Should not mint past the per-wallet limit", async () => {
+    const nftMint = findNftMint(player1.publicKey, 1);
+    const { instruction, signature } = signGenome(oracle, nftMint);
+
+    try {
+      await program.methods
+        .mintPet(null, signature)
+        .accountsPartial(extraMintAccounts(player1.publicKey, nftMint))
+        .preInstructions([instruction])
+        .signers([player1])
+        .rpc();
+      assert.fail("Second pet should exceed the per-wallet limit");
+    } catch (error) {
+      expect(error.toString()).to.include("WalletMintLimitReached");
+    }
+  });
+
+  it("Should mint an allowlisted pet during the presale", async () => {
+    // A single-leaf allowlist: the root is the leaf itself and the proof is empty.
+    const merkleRoot = Array.from(
+      createHash("sha256").update(player2.publicKey.toBuffer()).digest()
+    );
+    await program.methods
+      .updatePresale({
+        merkleRoot,
+        price: new anchor.BN(mintPrice / 2),
+        maxPerWallet: 1,
+        start: new anchor.BN(0),
+        end: new anchor.BN(Math.floor(Date.now() / 1000) + 24 * 60 * 60),
+      })
+      .accountsPartial({
+        admin: admin.publicKey,
+        gameConfig: gameConfig,
+      })
+      .signers([admin])
+      .rpc();
+
+    // Player 2 already used their public allowance, the presale one is separate.
+    const { instruction, signature } = signGenome(oracle, nftMintAddress4);
+    await program.methods
+      .mintPet([], signature)
+      .accountsPartial(extraMintAccounts(player2.publicKey, nftMintAddress4))
+      .preInstructions([instruction])
+      .signers([player2])
+      .rpc();
+
+    const [mintRecord] = PublicKey.findProgramAddressSync(
+      [Buffer.from("mint_record"), player2.publicKey.toBuffer()],
+      program.programId
+    );
+    const onChainRecord = await program.account.mintRecord.fetch(mintRecord);
+    expect(onChainRecord.minted).to.equal(2);
+    expect(onChainRecord.presaleMinted).to.equal(1);
+  });
+
+  it("Should reject a presale mint from a wallet not on the allowlist", async () => {
+    const nftMint = findNftMint(player1.publicKey, 1);
+    const { instruction, signature } = signGenome(oracle, nftMint);
+
+    try {
+      await program.methods
+        .mintPet([], signature)
+        .accountsPartial(extraMintAccounts(player1.publicKey, nftMint))
+        .preInstructions([instruction])
+        .signers([player1])
+        .rpc();
+      assert.fail("Wallet is not on the allowlist");
+    } catch (error) {
+      expect(error.toString()).to.include("NotOnAllowlist");
+    }
+  });
+
+  it("Should mint a pet for a third player", async () => {
+    const { instruction, signature } = signGenome(oracle, nftMintAddress3);
+    await program.methods
+      .mintPet(null, signature)
+      .accountsPartial(extraMintAccounts(player3.publicKey, nftMintAddress3))
+      .preInstructions([instruction])
+      .signers([player3])
+      .rpc();
+
+    const config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.petsMinted.toString()).to.equal("4");
   });
 
   it("Should freeze the nfts and init players", async () => {
@@ -378,6 +794,41 @@ describe("solapet-capstone", () => {
     expect(player2PetStats.energy.toString()).to.equal("100");
     expect(player2PetStats.hygiene.toString()).to.equal("100");
     expect(player2PetStats.hunger.toString()).to.equal("100");
+
+    // Stats are bound to the deposited pet and record who deposited it.
+    expect(player1PetStats.nftMint.toBase58()).to.equal(
+      nftMintAddress1.toBase58()
+    );
+    expect(player1PetStats.owner.toBase58()).to.equal(
+      player1.publicKey.toBase58()
+    );
+  });
+
+  it("Should deposit player 2's presale pet and player 3's pet", async () => {
+    await Promise.all([
+      program.methods
+        .initPlayer()
+        .accountsPartial(depositAccounts(player2.publicKey, nftMintAddress4))
+        .signers([player2])
+        .rpc(),
+      program.methods
+        .initPlayer()
+        .accountsPartial(depositAccounts(player3.publicKey, nftMintAddress3))
+        .signers([player3])
+        .rpc(),
+    ]);
+
+    const [player2PetStats, player3PetStats] = await Promise.all([
+      program.account.petStats.fetch(petStat4),
+      program.account.petStats.fetch(petStat3),
+    ]);
+    // A wallet can have several pets deposited at once.
+    expect(player2PetStats.owner.toBase58()).to.equal(
+      player2.publicKey.toBase58()
+    );
+    expect(player3PetStats.owner.toBase58()).to.equal(
+      player3.publicKey.toBase58()
+    );
   });
 
   it("Should not allow player to transfer the freezed nft's", async () => {
@@ -436,11 +887,18 @@ describe("solapet-capstone", () => {
 
   it("Should initialize a new duel challenge", async () => {
     await program.methods
-      .initPetDuel(new anchor.BN(0))
+      .initPetDuel(
+        new anchor.BN(0),
+        { commitReveal: {} },
+        player1Chain.commitment,
+        player2.publicKey
+      )
       .accountsPartial({
         challanger: player1.publicKey,
         gameConfig: gameConfig,
         gameVault: gameVault,
+        petStats: petStat1,
+        petDuelAccount: petDuel,
       })
       .signers([player1])
       .rpc();
@@ -450,35 +908,104 @@ describe("solapet-capstone", () => {
     expect(onChainDuel.challenger.toBase58()).to.equals(
       player1.publicKey.toBase58()
     );
+    expect(onChainDuel.challengerPet.toBase58()).to.equals(
+      nftMintAddress1.toBase58()
+    );
     expect(onChainDuel.betAmount.toString()).to.equals("0");
+    expect(onChainDuel.feeBps).to.equal(100);
     expect(onChainDuel.winner).to.equals(null);
+    // Freshly deposited pets are fully fed, clean and rested.
+    expect(onChainDuel.challengerCombat.maxHealth).to.equals(100);
+    expect(onChainDuel.challengerCombat.attackBonus).to.equals(10);
+    expect(onChainDuel.challengerCombat.critChance).to.equals(20);
     expect(onChainDuel.challengerPetHealth.toString()).to.equals("100");
-    expect(onChainDuel.defenderPetHealth.toString()).to.equals("100");
+    expect(onChainDuel.defenderPetHealth.toString()).to.equals("0");
+
+    // Entering the duel costs the challenger's pet energy, hunger and hygiene.
+    const challengerStats = await program.account.petStats.fetch(petStat1);
+    expect(challengerStats.energy).to.equals(80);
+    expect(challengerStats.hunger).to.equals(90);
+    expect(challengerStats.hygiene).to.equals(90);
     expect(onChainDuel.challengerTurn).to.equals(true);
+    expect(onChainDuel.defender.toBase58()).to.equals(
+      player2.publicKey.toBase58()
+    );
+  });
+
+  it("Should allow a second concurrent challenge from the same player", async () => {
+    await program.methods
+      .initPetDuel(
+        new anchor.BN(LAMPORTS_PER_SOL / 10),
+        { commitReveal: {} },
+        player1Chain.commitment,
+        null
+      )
+      .accountsPartial({
+        challanger: player1.publicKey,
+        gameConfig: gameConfig,
+        gameVault: gameVault,
+        petStats: petStat1,
+        petDuelAccount: pendingDuel,
+      })
+      .signers([player1])
+      .rpc();
+
+    const [onChainDuel, onChainStats] = await Promise.all([
+      program.account.petDuel.fetch(pendingDuel),
+      program.account.petStats.fetch(petStat1),
+    ]);
+
+    expect(onChainDuel.nonce.toString()).to.equals("1");
+    expect(onChainStats.duelNonce.toString()).to.equals("2");
+    expect(onChainStats.activeDuels).to.equals(2);
   });
 
-  it("Should not allowed to create another duel, when a duel is active", async () => {
+  it("Challenger should not be able to accept their own duel", async () => {
     try {
       await program.methods
-        .initPetDuel(new anchor.BN(0))
+        .acceptPetDuel(player1Chain.commitment)
         .accountsPartial({
-          challanger: player1.publicKey,
-          gameConfig: gameConfig,
-          gameVault: gameVault,
+          defender: player1.publicKey,
+          gameConfig,
+          petStats: petStat1,
+          petDuelAccount: pendingDuel,
         })
         .signers([player1])
         .rpc();
+      assert.fail("Challenger should not be able to accept their own duel");
+    } catch (error) {
+      expect(error.toString()).to.include("CannotChallengeSelf");
+    }
+  });
+
+  it("Should not let a third party accept a targeted duel", async () => {
+    try {
+      await program.methods
+        .acceptPetDuel(Array.from(randomBytes(32)))
+        .accountsPartial({
+          defender: player3.publicKey,
+          gameConfig,
+          petStats: petStat3,
+          petDuelAccount: petDuel,
+        })
+        .signers([player3])
+        .rpc();
+      assert.fail("Only the named opponent can accept");
     } catch (error) {
-      assert.isOk(error.message, "AlreadyInitialized");
+      expect(error.toString()).to.include("OnlyDefenderAction");
     }
+
+    // The failed attempt charged nothing.
+    const thirdPartyStats = await program.account.petStats.fetch(petStat3);
+    expect(thirdPartyStats.energy).to.equal(100);
+    expect(thirdPartyStats.activeDuels).to.equal(0);
   });
 
-  it("Should accept an existing duel", async () => {
+  it("Named opponent should be able to accept a targeted duel", async () => {
     await program.methods
-      .acceptPetDuel()
+      .acceptPetDuel(player2Chain.commitment)
       .accountsPartial({
         defender: player2.publicKey,
-        challenger: player1.publicKey,
         gameConfig,
         petStats: petStat2,
         petDuelAccount: petDuel,
@@ -490,63 +1017,60 @@ describe("solapet-capstone", () => {
     expect(onChainDuel.defender.toBase58()).to.equals(
       player2.publicKey.toBase58()
     );
+    expect(onChainDuel.defenderPetHealth).to.equals(
+      onChainDuel.defenderCombat.maxHealth
+    );
   });
 
   it("Defender should not allowed to make the first move", async () => {
-    const account = await provider.connection.getAccountInfo(
-      petDuel,
-      "confirmed"
-    );
-
-    let sig_ix = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: player2.secretKey,
-      message: account.data.subarray(8),
-    });
-
-    const attack_ix = await program.methods
-      .petAttack(Buffer.from(sig_ix.data.buffer.slice(16 + 32, 16 + 32 + 64)))
-      .accountsPartial({
-        attacker: player2.publicKey,
-        challanger: player1.publicKey,
-        petDuelAccount: petDuel,
-        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
-      })
-      .signers([player2])
-      .instruction();
-
-    const tx = new Transaction().add(sig_ix).add(attack_ix);
     try {
-      await sendAndConfirmTransaction(provider.connection, tx, [player2]);
+      await program.methods
+        .petAttack(player2Chain.peek(), { attack: {} })
+        .accountsPartial({
+          attacker: player2.publicKey,
+          petDuelAccount: petDuel,
+          challengerPetStats: petStat1,
+          defenderPetStats: petStat2,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([player2])
+        .rpc();
     } catch (error) {
       assert.isOk(error.message, "NotChallengerTurn.");
     }
   });
 
-  it("challanger should allowed to perform attack", async () => {
-    const account = await provider.connection.getAccountInfo(
-      petDuel,
-      "confirmed"
-    );
-
-    const sig_ix = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: player1.secretKey,
-      message: account.data.subarray(8),
-    });
+  it("Should reject a reveal that does not match the commitment", async () => {
+    try {
+      await program.methods
+        .petAttack(randomBytes(32), { attack: {} })
+        .accountsPartial({
+          attacker: player1.publicKey,
+          petDuelAccount: petDuel,
+          challengerPetStats: petStat1,
+          defenderPetStats: petStat2,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([player1])
+        .rpc();
+      assert.fail("Attack with a forged reveal should have failed");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidReveal");
+    }
+  });
 
-    const attack_ix = await program.methods
-      .petAttack(Buffer.from(sig_ix.data.buffer.slice(16 + 32, 16 + 32 + 64)))
+  it("challanger should allowed to perform attack", async () => {
+    await program.methods
+      .petAttack(player1Chain.reveal(), { attack: {} })
       .accountsPartial({
         attacker: player1.publicKey,
-        challanger: player1.publicKey,
         petDuelAccount: petDuel,
+        challengerPetStats: petStat1,
+        defenderPetStats: petStat2,
         instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
       })
       .signers([player1])
-      .instruction();
-
-    const tx = new Transaction().add(sig_ix).add(attack_ix);
-
-    await sendAndConfirmTransaction(provider.connection, tx, [player1]);
+      .rpc();
 
     const onChainDuel = await program.account.petDuel.fetch(petDuel);
 
@@ -555,52 +1079,31 @@ describe("solapet-capstone", () => {
   });
 
   it("Challanger should not be allowed to perfrom attack", async () => {
-    const account = await provider.connection.getAccountInfo(
-      petDuel,
-      "confirmed"
-    );
-
-    let sig_ix = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: player1.secretKey,
-      message: account.data.subarray(8),
-    });
-
-    const attack_ix = await program.methods
-      .petAttack(Buffer.from(sig_ix.data.buffer.slice(16 + 32, 16 + 32 + 64)))
-      .accountsPartial({
-        attacker: player1.publicKey,
-        challanger: player1.publicKey,
-        petDuelAccount: petDuel,
-        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
-      })
-      .signers([player1])
-      .instruction();
-
-    const tx = new Transaction().add(sig_ix).add(attack_ix);
     try {
-      await sendAndConfirmTransaction(provider.connection, tx, [player1]);
+      await program.methods
+        .petAttack(player1Chain.peek(), { attack: {} })
+        .accountsPartial({
+          attacker: player1.publicKey,
+          petDuelAccount: petDuel,
+          challengerPetStats: petStat1,
+          defenderPetStats: petStat2,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([player1])
+        .rpc();
     } catch (error) {
       assert.isOk(error.message, "NotDefenderTurn");
     }
   });
 
   it("Defender should allowed to perfrom attack", async () => {
-    const account = await provider.connection.getAccountInfo(
-      petDuel,
-      "confirmed"
-    );
-
-    let sig_ix = Ed25519Program.createInstructionWithPrivateKey({
-      privateKey: player2.secretKey,
-      message: account.data.subarray(8),
-    });
-
     await program.methods
-      .petAttack(Buffer.from(sig_ix.data.buffer.slice(16 + 32, 16 + 32 + 64)))
+      .petAttack(player2Chain.reveal(), { attack: {} })
       .accountsPartial({
         attacker: player2.publicKey,
-        challanger: player1.publicKey,
         petDuelAccount: petDuel,
+        challengerPetStats: petStat1,
+        defenderPetStats: petStat2,
         instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
       })
       .signers([player2])
@@ -612,6 +1115,64 @@ describe("solapet-capstone", () => {
     expect(onChainDuel.challengerPetHealth).to.lessThan(100);
   });
 
+  it("Challenger should be able to guard instead of attacking", async () => {
+    const before = await program.account.petDuel.fetch(petDuel);
+
+    await program.methods
+      .petAttack(player1Chain.reveal(), { guard: {} })
+      .accountsPartial({
+        attacker: player1.publicKey,
+        petDuelAccount: petDuel,
+        challengerPetStats: petStat1,
+        defenderPetStats: petStat2,
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .signers([player1])
+      .rpc();
+
+    const onChainDuel = await program.account.petDuel.fetch(petDuel);
+
+    expect(onChainDuel.challengerState.guarding).to.equal(true);
+    expect(onChainDuel.challengerTurn).to.equal(false);
+    expect(onChainDuel.defenderPetHealth).to.equal(before.defenderPetHealth);
+  });
+
+  it("Heal should stay on cooldown for the pet's next two turns", async () => {
+    const act = (player: Keypair, reveal: Buffer, action) =>
+      program.methods
+        .petAttack(reveal, action)
+        .accountsPartial({
+          attacker: player.publicKey,
+          petDuelAccount: petDuel,
+          challengerPetStats: petStat1,
+          defenderPetStats: petStat2,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([player])
+        .rpc();
+
+    // Nobody takes damage while both sides only guard and heal.
+    await act(player2, player2Chain.reveal(), { heal: {} });
+    let onChainDuel = await program.account.petDuel.fetch(petDuel);
+    expect(onChainDuel.defenderState.healCooldown).to.equal(2);
+
+    for (let turn = 0; turn < 2; turn++) {
+      await act(player1, player1Chain.reveal(), { guard: {} });
+      try {
+        await act(player2, player2Chain.peek(), { heal: {} });
+        assert.fail("Heal should still be cooling down");
+      } catch (error) {
+        expect(error.toString()).to.include("ActionOnCooldown");
+      }
+      await act(player2, player2Chain.reveal(), { guard: {} });
+    }
+
+    await act(player1, player1Chain.reveal(), { guard: {} });
+    await act(player2, player2Chain.reveal(), { heal: {} });
+    onChainDuel = await program.account.petDuel.fetch(petDuel);
+    expect(onChainDuel.defenderState.healCooldown).to.equal(2);
+  });
+
   it("Fight should end when pet health drops to zero", async () => {
     let duelAccount = await program.account.petDuel.fetch(petDuel);
 
@@ -619,23 +1180,17 @@ describe("solapet-capstone", () => {
       duelAccount.challengerPetHealth > 0 &&
       duelAccount.defenderPetHealth > 0
     ) {
-      const account = await provider.connection.getAccountInfo(
-        petDuel,
-        "confirmed"
-      );
-
-      const currentAttacker = duelAccount.challengerTurn ? player1 : player2;
-      let sig_ix = Ed25519Program.createInstructionWithPrivateKey({
-        privateKey: currentAttacker.secretKey,
-        message: account.data.subarray(8),
-      });
+      const [currentAttacker, chain] = duelAccount.challengerTurn
+        ? [player1, player1Chain]
+        : [player2, player2Chain];
 
       await program.methods
-        .petAttack(Buffer.from(sig_ix.data.buffer.slice(16 + 32, 16 + 32 + 64)))
+        .petAttack(chain.reveal(), { attack: {} })
         .accountsPartial({
           attacker: currentAttacker.publicKey,
-          challanger: player1.publicKey,
           petDuelAccount: petDuel,
+          challengerPetStats: petStat1,
+          defenderPetStats: petStat2,
           instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
         })
         .signers([currentAttacker])
@@ -645,6 +1200,22 @@ describe("solapet-capstone", () => {
     const finalDuel = await program.account.petDuel.fetch(petDuel);
 
     expect(finalDuel.winner).to.not.equal(null);
+
+    const challengerWon = finalDuel.winner.equals(player1.publicKey);
+    const [winnerStats, loserStats] = await Promise.all([
+      program.account.petStats.fetch(challengerWon ? petStat1 : petStat2),
+      program.account.petStats.fetch(challengerWon ? petStat2 : petStat1),
+    ]);
+
+    // The beaten pet is left exhausted.
+    expect(loserStats.energy).to.equals(0);
+
+    expect(winnerStats.wins).to.equals(1);
+    expect(winnerStats.winStreak).to.equals(1);
+    expect(winnerStats.experience.toString()).to.equals("100");
+    expect(loserStats.losses).to.equals(1);
+    expect(loserStats.winStreak).to.equals(0);
+    expect(loserStats.experience.toString()).to.equals("25");
   });
 
   it("non winner should not be allowed to claim the win amount", async () => {
@@ -663,9 +1234,11 @@ describe("solapet-capstone", () => {
         .claimBet()
         .accountsPartial({
           winner: non_winner.publicKey,
-          challanger: player1.publicKey,
           gameConfig,
           petDuelAccount: petDuel,
+          winnerPetStats: onChainDuel.winner.equals(player1.publicKey)
+            ? petStat1
+            : petStat2,
           gameVault,
         })
         .signers([non_winner])
@@ -677,27 +1250,36 @@ describe("solapet-capstone", () => {
 
   it("Winner should be allowed to claim the win amount", async () => {
     let winner: Keypair;
+    let winnerPetStats: PublicKey;
 
     const onChainDuel = await program.account.petDuel.fetch(petDuel);
 
     if (onChainDuel.winner.equals(player1.publicKey)) {
       winner = player1;
+      winnerPetStats = petStat1;
     } else {
       winner = player2;
+      winnerPetStats = petStat2;
     }
 
+    // The winning pet keeps its duel slot until the pot is claimed.
+    const statsBefore = await program.account.petStats.fetch(winnerPetStats);
+
     await program.methods
       .claimBet()
       .accountsPartial({
         winner: onChainDuel.winner,
-        challanger: player1.publicKey,
         gameConfig,
         petDuelAccount: petDuel,
+        winnerPetStats,
         gameVault,
       })
       .signers([winner])
       .rpc();
 
+    const statsAfter = await program.account.petStats.fetch(winnerPetStats);
+    expect(statsBefore.activeDuels - statsAfter.activeDuels).to.equal(1);
+
     try {
       await program.account.petDuel.fetch(petDuel);
     } catch (error) {
@@ -705,6 +1287,810 @@ describe("solapet-capstone", () => {
     }
   });
 
+  it("Should not withdraw a pet that still has an open challenge", async () => {
+    try {
+      await program.methods
+        .closePlayer()
+        .accountsPartial({
+          player: player1.publicKey,
+          collectionMint: collectionMint.publicKey,
+          nftMint: nftMintAddress1,
+          playerAta: player1ATA,
+          config: gameConfig,
+          masterEdition: masterEdition1,
+          metadata: metadata1,
+          petStats: petStat1,
+          tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+          sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([player1])
+        .rpc();
+      assert.fail("Pet should not leave while a duel is unfinished");
+    } catch (error) {
+      expect(error.toString()).to.include("PetInActiveDuel");
+    }
+  });
+
+  it("Challenger should be able to cancel an unaccepted duel and get the bet back", async () => {
+    const { betAmount, challengerCost } = await program.account.petDuel.fetch(
+      pendingDuel
+    );
+    const statsBefore = await program.account.petStats.fetch(petStat1);
+    const vaultBalanceBefore = await provider.connection.getBalance(gameVault);
+
+    await program.methods
+      .cancelPetDuel()
+      .accountsPartial({
+        caller: player1.publicKey,
+        challanger: player1.publicKey,
+        gameConfig,
+        gameVault,
+        petDuelAccount: pendingDuel,
+        petStats: petStat1,
+      })
+      .signers([player1])
+      .rpc();
+
+    const vaultBalanceAfter = await provider.connection.getBalance(gameVault);
+    expect(vaultBalanceBefore - vaultBalanceAfter).to.equal(
+      betAmount.toNumber()
+    );
+
+    const closedDuel = await provider.connection.getAccountInfo(pendingDuel);
+    expect(closedDuel).to.equal(null);
+
+    const onChainStats = await program.account.petStats.fetch(petStat1);
+    expect(onChainStats.activeDuels).to.equals(0);
+    // The pet gets back what entering the challenge actually took from it.
+    expect(challengerCost.energy).to.equals(20);
+    expect(challengerCost.hunger).to.be.at.most(10);
+    expect(challengerCost.hygiene).to.be.at.most(10);
+    expect(onChainStats.energy).to.equals(
+      Math.min(100, statsBefore.energy + challengerCost.energy)
+    );
+    expect(onChainStats.hunger).to.equals(
+      Math.min(100, statsBefore.hunger + challengerCost.hunger)
+    );
+    expect(onChainStats.hygiene).to.equals(
+      Math.min(100, statsBefore.hygiene + challengerCost.hygiene)
+    );
+  });
+
+  it("Should start a duel in oracle randomness mode", async () => {
+    // Oracle duels take no hash-chain commitments.
+    const noCommitment = new Array(32).fill(0);
+
+    await program.methods
+      .initPetDuel(new anchor.BN(0), { oracle: {} }, noCommitment, null)
+      .accountsPartial({
+        challanger: player3.publicKey,
+        gameConfig,
+        gameVault,
+        petStats: petStat3,
+        petDuelAccount: oracleDuel,
+      })
+      .signers([player3])
+      .rpc();
+
+    await program.methods
+      .acceptPetDuel(noCommitment)
+      .accountsPartial({
+        defender: player2.publicKey,
+        gameConfig,
+        petStats: petStat4,
+        petDuelAccount: oracleDuel,
+      })
+      .signers([player2])
+      .rpc();
+
+    const onChainDuel = await program.account.petDuel.fetch(oracleDuel);
+    expect(onChainDuel.randomnessMode).to.deep.equal({ oracle: {} });
+    expect(onChainDuel.duelStatus).to.deep.equal({ started: {} });
+  });
+
+  it("Should reject an oracle attack without a signature instruction", async () => {
+    const { signature } = signTurn(oracle, oracleDuel, 0, player3.publicKey);
+
+    try {
+      await program.methods
+        .petAttack(signature, { attack: {} })
+        .accountsPartial({
+          attacker: player3.publicKey,
+          petDuelAccount: oracleDuel,
+          challengerPetStats: petStat3,
+          defenderPetStats: petStat4,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .signers([player3])
+        .rpc();
+      assert.fail("The signature must be verified in the same transaction");
+    } catch (error) {
+      expect(error.toString()).to.include("SignatureInstructionMissing");
+    }
+  });
+
+  it("Should reject an oracle signature from the wrong signer", async () => {
+    const { instruction, signature } = signTurn(
+      Keypair.generate(),
+      oracleDuel,
+      0,
+      player3.publicKey
+    );
+
+    try {
+      await program.methods
+        .petAttack(signature, { attack: {} })
+        .accountsPartial({
+          attacker: player3.publicKey,
+          petDuelAccount: oracleDuel,
+          challengerPetStats: petStat3,
+          defenderPetStats: petStat4,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .preInstructions([instruction])
+        .signers([player3])
+        .rpc();
+      assert.fail("Only the randomness authority may sign turns");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidSigner");
+    }
+  });
+
+  it("Should reject an oracle signature over the wrong message", async () => {
+    // Signed for a later turn, so it cannot be replayed for this one.
+    const { instruction, signature } = signTurn(
+      oracle,
+      oracleDuel,
+      1,
+      player3.publicKey
+    );
+
+    try {
+      await program.methods
+        .petAttack(signature, { attack: {} })
+        .accountsPartial({
+          attacker: player3.publicKey,
+          petDuelAccount: oracleDuel,
+          challengerPetStats: petStat3,
+          defenderPetStats: petStat4,
+          instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .preInstructions([instruction])
+        .signers([player3])
+        .rpc();
+      assert.fail("The signature must cover this turn");
+    } catch (error) {
+      expect(error.toString()).to.include("SigDataNoMatch");
+    }
+  });
+
+  it("Should accept the oracle's signature at any earlier instruction index", async () => {
+    const { instruction, signature } = signTurn(
+      oracle,
+      oracleDuel,
+      0,
+      player3.publicKey
+    );
+
+    await program.methods
+      .petAttack(signature, { attack: {} })
+      .accountsPartial({
+        attacker: player3.publicKey,
+        petDuelAccount: oracleDuel,
+        challengerPetStats: petStat3,
+        defenderPetStats: petStat4,
+        instructionsSysvar: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .preInstructions([
+        ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 }),
+        instruction,
+      ])
+      .signers([player3])
+      .rpc();
+
+    const onChainDuel = await program.account.petDuel.fetch(oracleDuel);
+    expect(onChainDuel.turn).to.equal(1);
+    expect(onChainDuel.challengerTurn).to.equal(false);
+    expect(onChainDuel.defenderPetHealth).to.lessThan(
+      onChainDuel.defenderCombat.maxHealth
+    );
+  });
+
+  it("Should not let a forfeit be claimed right after an old challenge is accepted", async () => {
+    // A short timeout, so the test can wait it out.
+    await program.methods
+      .updateTurnTimeout(new anchor.BN(3))
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+
+    await program.methods
+      .initPetDuel(
+        new anchor.BN(0),
+        { oracle: {} },
+        new Array(32).fill(0),
+        player2.publicKey
+      )
+      .accountsPartial({
+        challanger: player3.publicKey,
+        gameConfig,
+        gameVault,
+        petStats: petStat3,
+        petDuelAccount: forfeitDuel,
+      })
+      .signers([player3])
+      .rpc();
+
+    // The challenge is older than the turn timeout by the time it is accepted.
+    await sleep(5_000);
+    await program.methods
+      .acceptPetDuel(new Array(32).fill(0))
+      .accountsPartial({
+        defender: player2.publicKey,
+        gameConfig,
+        petStats: petStat4,
+        petDuelAccount: forfeitDuel,
+      })
+      .signers([player2])
+      .rpc();
+
+    try {
+      await program.methods
+        .claimForfeit()
+        .accountsPartial({
+          claimant: player2.publicKey,
+          petDuelAccount: forfeitDuel,
+          challengerPetStats: petStat3,
+          defenderPetStats: petStat4,
+        })
+        .signers([player2])
+        .rpc();
+      assert.fail("The challenger has not had a turn yet");
+    } catch (error) {
+      expect(error.toString()).to.include("TurnTimeoutNotElapsed");
+    }
+  });
+
+  it("Should not let the stalling player claim a forfeit", async () => {
+    try {
+      await program.methods
+        .claimForfeit()
+        .accountsPartial({
+          claimant: player3.publicKey,
+          petDuelAccount: forfeitDuel,
+          challengerPetStats: petStat3,
+          defenderPetStats: petStat4,
+        })
+        .signers([player3])
+        .rpc();
+      assert.fail("Only the waiting player can claim a forfeit");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedAction");
+    }
+  });
+
+  it("Should let the waiting player claim a forfeit once the turn times out", async () => {
+    await sleep(5_000);
+    await program.methods
+      .claimForfeit()
+      .accountsPartial({
+        claimant: player2.publicKey,
+        petDuelAccount: forfeitDuel,
+        challengerPetStats: petStat3,
+        defenderPetStats: petStat4,
+      })
+      .signers([player2])
+      .rpc();
+
+    const [onChainDuel, loserStats] = await Promise.all([
+      program.account.petDuel.fetch(forfeitDuel),
+      program.account.petStats.fetch(petStat3),
+    ]);
+    expect(onChainDuel.duelStatus).to.deep.equal({ finished: {} });
+    expect(onChainDuel.winner.toBase58()).to.equal(
+      player2.publicKey.toBase58()
+    );
+    expect(loserStats.losses).to.equal(1);
+
+    // The pot is paid out like any other finished duel.
+    await program.methods
+      .claimBet()
+      .accountsPartial({
+        winner: player2.publicKey,
+        gameConfig,
+        petDuelAccount: forfeitDuel,
+        winnerPetStats: petStat4,
+        gameVault,
+      })
+      .signers([player2])
+      .rpc();
+    expect(await provider.connection.getAccountInfo(forfeitDuel)).to.equal(
+      null
+    );
+
+    await program.methods
+      .updateTurnTimeout(new anchor.BN(24 * 60 * 60))
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+  });
+
+  it("Should not let a third party surrender a duel", async () => {
+    try {
+      await program.methods
+        .surrenderPetDuel()
+        .accountsPartial({
+          player: player1.publicKey,
+          petDuelAccount: oracleDuel,
+          challengerPetStats: petStat3,
+          defenderPetStats: petStat4,
+        })
+        .signers([player1])
+        .rpc();
+      assert.fail("Only a duelist may surrender");
+    } catch (error) {
+      expect(error.toString()).to.include("UnauthorizedAction");
+    }
+  });
+
+  it("Should let a duelist surrender, handing the win to the opponent", async () => {
+    const [loserBefore, winnerBefore] = await Promise.all([
+      program.account.petStats.fetch(petStat3),
+      program.account.petStats.fetch(petStat4),
+    ]);
+
+    await program.methods
+      .surrenderPetDuel()
+      .accountsPartial({
+        player: player3.publicKey,
+        petDuelAccount: oracleDuel,
+        challengerPetStats: petStat3,
+        defenderPetStats: petStat4,
+      })
+      .signers([player3])
+      .rpc();
+
+    const [onChainDuel, loserStats, winnerStats] = await Promise.all([
+      program.account.petDuel.fetch(oracleDuel),
+      program.account.petStats.fetch(petStat3),
+      program.account.petStats.fetch(petStat4),
+    ]);
+    expect(onChainDuel.duelStatus).to.deep.equal({ finished: {} });
+    expect(onChainDuel.winner.toBase58()).to.equal(
+      player2.publicKey.toBase58()
+    );
+    expect(loserStats.losses).to.equal(loserBefore.losses + 1);
+    expect(loserStats.activeDuels).to.equal(loserBefore.activeDuels - 1);
+    expect(winnerStats.wins).to.equal(winnerBefore.wins + 1);
+    // The winner holds its duel slot until the pot is claimed.
+    expect(winnerStats.activeDuels).to.equal(winnerBefore.activeDuels);
+  });
+
+  it("Should not surrender a duel that is already finished", async () => {
+    try {
+      await program.methods
+        .surrenderPetDuel()
+        .accountsPartial({
+          player: player2.publicKey,
+          petDuelAccount: oracleDuel,
+          challengerPetStats: petStat3,
+          defenderPetStats: petStat4,
+        })
+        .signers([player2])
+        .rpc();
+      assert.fail("The duel is already over");
+    } catch (error) {
+      expect(error.toString()).to.include("DuelFinished");
+    }
+  });
+
+  it("Should let the pet that surrendered be withdrawn right away", async () => {
+    await program.methods
+      .closePlayer()
+      .accountsPartial(depositAccounts(player3.publicKey, nftMintAddress3))
+      .signers([player3])
+      .rpc();
+
+    const onChainStats = await program.account.petStats.fetch(petStat3);
+    expect(onChainStats.owner.toBase58()).to.equal(
+      PublicKey.default.toBase58()
+    );
+  });
+
+  it("Should only withdraw the winning pet once the surrendered pot is claimed", async () => {
+    try {
+      await program.methods
+        .closePlayer()
+        .accountsPartial(depositAccounts(player2.publicKey, nftMintAddress4))
+        .signers([player2])
+        .rpc();
+      assert.fail("The winner's pot is still unclaimed");
+    } catch (error) {
+      expect(error.toString()).to.include("PetInActiveDuel");
+    }
+
+    await program.methods
+      .claimBet()
+      .accountsPartial({
+        winner: player2.publicKey,
+        gameConfig,
+        petDuelAccount: oracleDuel,
+        winnerPetStats: petStat4,
+        gameVault,
+      })
+      .signers([player2])
+      .rpc();
+
+    await program.methods
+      .closePlayer()
+      .accountsPartial(depositAccounts(player2.publicKey, nftMintAddress4))
+      .signers([player2])
+      .rpc();
+
+    const onChainStats = await program.account.petStats.fetch(petStat4);
+    expect(onChainStats.activeDuels).to.equal(0);
+    expect(onChainStats.owner.toBase58()).to.equal(
+      PublicKey.default.toBase58()
+    );
+  });
+
+  it("Should not withdraw more than the accrued fees", async () => {
+    const { accruedFees } = await program.account.gameConfig.fetch(gameConfig);
+
+    try {
+      await program.methods
+        .withdrawFees(accruedFees.addn(1))
+        .accountsPartial({ feeManager: admin.publicKey, gameConfig, gameVault })
+        .signers([admin])
+        .rpc();
+      assert.fail("Should not withdraw escrowed funds");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientFees");
+    }
+  });
+
+  it("Should let the admin withdraw accrued fees", async () => {
+    const configBefore = await program.account.gameConfig.fetch(gameConfig);
+    const vaultBalanceBefore = await provider.connection.getBalance(gameVault);
+
+    // Mint proceeds are house revenue, and no bet is left in escrow.
+    expect(configBefore.accruedFees.toNumber()).to.be.greaterThan(0);
+    expect(configBefore.totalEscrow.toString()).to.equal("0");
+
+    const amount = configBefore.accruedFees.divn(2);
+    await program.methods
+      .withdrawFees(amount)
+      .accountsPartial({ feeManager: admin.publicKey, gameConfig, gameVault })
+      .signers([admin])
+      .rpc();
+
+    const configAfter = await program.account.gameConfig.fetch(gameConfig);
+    const vaultBalanceAfter = await provider.connection.getBalance(gameVault);
+    expect(vaultBalanceBefore - vaultBalanceAfter).to.equal(amount.toNumber());
+    expect(configAfter.accruedFees.toString()).to.equal(
+      configBefore.accruedFees.sub(amount).toString()
+    );
+  });
+
+  it("Should accept an SPL token for bets", async () => {
+    betMint = await createMint(
+      provider.connection,
+      admin,
+      admin.publicKey,
+      null,
+      6
+    );
+
+    await program.methods
+      .updateBetMint(true)
+      .accountsPartial({
+        admin: admin.publicKey,
+        gameConfig,
+        gameVault,
+        betMint,
+        vaultTokenAccount: getAssociatedTokenAddressSync(
+          betMint,
+          gameVault,
+          true
+        ),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([admin])
+      .rpc();
+
+    const { betMints } = await program.account.gameConfig.fetch(gameConfig);
+    expect(betMints).to.have.lengthOf(1);
+    expect(betMints[0].mint.toBase58()).to.equal(betMint.toBase58());
+    expect(betMints[0].enabled).to.equal(true);
+  });
+
+  it("Should not accept a Token-2022 mint with a transfer fee for bets", async () => {
+    const feeMint = Keypair.generate();
+    const space = getMintLen([ExtensionType.TransferFeeConfig]);
+    const transaction = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: admin.publicKey,
+        newAccountPubkey: feeMint.publicKey,
+        space,
+        lamports:
+          await provider.connection.getMinimumBalanceForRentExemption(space),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializeTransferFeeConfigInstruction(
+        feeMint.publicKey,
+        admin.publicKey,
+        admin.publicKey,
+        100,
+        BigInt(1_000_000),
+        TOKEN_2022_PROGRAM_ID
+      ),
+      createInitializeMintInstruction(
+        feeMint.publicKey,
+        6,
+        admin.publicKey,
+        null,
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    await sendAndConfirmTransaction(provider.connection, transaction, [
+      admin,
+      feeMint,
+    ]);
+
+    try {
+      await program.methods
+        .updateBetMint(true)
+        .accountsPartial({
+          admin: admin.publicKey,
+          gameConfig,
+          gameVault,
+          betMint: feeMint.publicKey,
+          vaultTokenAccount: getAssociatedTokenAddressSync(
+            feeMint.publicKey,
+            gameVault,
+            true,
+            TOKEN_2022_PROGRAM_ID
+          ),
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .signers([admin])
+        .rpc();
+      assert.fail("The vault would receive less than the escrowed bets");
+    } catch (error) {
+      expect(error.toString()).to.include("TransferFeeMintNotSupported");
+    }
+
+    const { betMints } = await program.account.gameConfig.fetch(gameConfig);
+    expect(betMints).to.have.lengthOf(1);
+  });
+
+  it("Should reject a duel bet in a disabled mint", async () => {
+    await program.methods
+      .updateBetMint(false)
+      .accountsPartial({
+        admin: admin.publicKey,
+        gameConfig,
+        gameVault,
+        betMint,
+        vaultTokenAccount: getAssociatedTokenAddressSync(
+          betMint,
+          gameVault,
+          true
+        ),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([admin])
+      .rpc();
+
+    const [{ duelNonce }, player1TokenAccount] = await Promise.all([
+      program.account.petStats.fetch(petStat1),
+      getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        player1,
+        betMint,
+        player1.publicKey
+      ),
+    ]);
+
+    try {
+      await program.methods
+        .initPetDuel(
+          new anchor.BN(1_000_000),
+          { commitReveal: {} },
+          player1Chain.commitment,
+          null
+        )
+        .accountsPartial({
+          challanger: player1.publicKey,
+          gameConfig,
+          gameVault,
+          petStats: petStat1,
+          petDuelAccount: findPetDuel(nftMintAddress1, duelNonce.toNumber()),
+          betMint,
+          challangerTokenAccount: player1TokenAccount.address,
+          vaultTokenAccount: getAssociatedTokenAddressSync(
+            betMint,
+            gameVault,
+            true
+          ),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([player1])
+        .rpc();
+      assert.fail("Disabled bet mints should be rejected");
+    } catch (error) {
+      expect(error.toString()).to.include("BetMintNotAllowed");
+    }
+  });
+
+  it("Should only let the pauser pause the game", async () => {
+    try {
+      await program.methods
+        .setPaused(true)
+        .accountsPartial({ pauser: player1.publicKey, gameConfig })
+        .signers([player1])
+        .rpc();
+      assert.fail("Only the pauser can pause the game");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidAdminAccess");
+    }
+  });
+
+  it("Should block new duels while paused", async () => {
+    await program.methods
+      .setPaused(true)
+      .accountsPartial({ pauser: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+
+    const { duelNonce } = await program.account.petStats.fetch(petStat1);
+    try {
+      await program.methods
+        .initPetDuel(
+          new anchor.BN(0),
+          { commitReveal: {} },
+          player1Chain.commitment,
+          null
+        )
+        .accountsPartial({
+          challanger: player1.publicKey,
+          gameConfig,
+          gameVault,
+          petStats: petStat1,
+          petDuelAccount: findPetDuel(nftMintAddress1, duelNonce.toNumber()),
+        })
+        .signers([player1])
+        .rpc();
+      assert.fail("Duels should not start while the game is paused");
+    } catch (error) {
+      expect(error.toString()).to.include("GamePaused");
+    }
+
+    await program.methods
+      .setPaused(false)
+      .accountsPartial({ pauser: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+    const config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.paused).to.be.false;
+  });
+
+  it("Should let the admin hand the fee manager role to another key", async () => {
+    await program.methods
+      .updateRole({ feeManager: {} }, player2.publicKey)
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+
+    try {
+      await program.methods
+        .updateFees(200)
+        .accountsPartial({ feeManager: admin.publicKey, gameConfig })
+        .signers([admin])
+        .rpc();
+      assert.fail("The admin is no longer the fee manager");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidAdminAccess");
+    }
+
+    await program.methods
+      .updateRole({ feeManager: {} }, admin.publicKey)
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+    const config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.feeManager.toBase58()).to.equal(admin.publicKey.toBase58());
+  });
+
+  it("Should let the admin hand the randomness authority role to another key", async () => {
+    const newOracle = Keypair.generate();
+    await program.methods
+      .updateRole({ randomnessAuthority: {} }, newOracle.publicKey)
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+
+    let config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.randomnessAuthority.toBase58()).to.equal(
+      newOracle.publicKey.toBase58()
+    );
+
+    await program.methods
+      .updateRole({ randomnessAuthority: {} }, oracle.publicKey)
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+    config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.randomnessAuthority.toBase58()).to.equal(
+      oracle.publicKey.toBase58()
+    );
+  });
+
+  it("Should only let the admin change the turn timeout", async () => {
+    try {
+      await program.methods
+        .updateTurnTimeout(new anchor.BN(60))
+        .accountsPartial({ admin: player2.publicKey, gameConfig })
+        .signers([player2])
+        .rpc();
+      assert.fail("Only the admin may change the turn timeout");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidAdminAccess");
+    }
+  });
+
+  it("Should hand the admin role over in two steps", async () => {
+    await program.methods
+      .proposeAdmin(player2.publicKey)
+      .accountsPartial({ admin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+
+    let config = await program.account.gameConfig.fetch(gameConfig);
+    // Proposing alone does not change the admin.
+    expect(config.admin.toBase58()).to.equal(admin.publicKey.toBase58());
+    expect(config.pendingAdmin.toBase58()).to.equal(
+      player2.publicKey.toBase58()
+    );
+
+    try {
+      await program.methods
+        .acceptAdmin()
+        .accountsPartial({ newAdmin: player1.publicKey, gameConfig })
+        .signers([player1])
+        .rpc();
+      assert.fail("Only the proposed key can accept");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidAdminAccess");
+    }
+
+    await program.methods
+      .acceptAdmin()
+      .accountsPartial({ newAdmin: player2.publicKey, gameConfig })
+      .signers([player2])
+      .rpc();
+
+    config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.admin.toBase58()).to.equal(player2.publicKey.toBase58());
+    expect(config.pendingAdmin).to.be.null;
+
+    // Hand it back so the remaining tests keep using the original admin.
+    await program.methods
+      .proposeAdmin(admin.publicKey)
+      .accountsPartial({ admin: player2.publicKey, gameConfig })
+      .signers([player2])
+      .rpc();
+    await program.methods
+      .acceptAdmin()
+      .accountsPartial({ newAdmin: admin.publicKey, gameConfig })
+      .signers([admin])
+      .rpc();
+
+    config = await program.account.gameConfig.fetch(gameConfig);
+    expect(config.admin.toBase58()).to.equal(admin.publicKey.toBase58());
+  });
+
   it("Should interact with pet", async () => {
     await Promise.all([
       program.methods
@@ -720,7 +2106,60 @@ describe("solapet-capstone", () => {
     ]);
   });
 
-  it("Should unlock the NFT, revoke delegate, and close all the stats accounts", async () => {
+  it("Should not let a pet sleep before it has rested long enough", async () => {
+    try {
+      await program.methods
+        .petInteract({ sleep: {} })
+        .accountsPartial({ player: player1.publicKey, petStats: petStat1 })
+        .signers([player1])
+        .rpc();
+      assert.fail("The pet was deposited less than 7.5 hours ago");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientPetEnergy");
+    }
+  });
+
+  it("Should refresh the pet metadata from its on-chain state", async () => {
+    await program.methods
+      .refreshPetMetadata()
+      .accountsPartial({
+        payer: player2.publicKey,
+        gameConfig,
+        nftMint: nftMintAddress1,
+        petStats: petStat1,
+        metadata: metadata1,
+        tokenMetadataProgram: MPL_TOKEN_METADATA_PROGRAM_ID,
+        sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .signers([player2])
+      .rpc();
+
+    const [petGenome] = PublicKey.findProgramAddressSync(
+      [Buffer.from("genome"), nftMintAddress1.toBuffer()],
+      program.programId
+    );
+    const [genome, stats, onChainMetadata] = await Promise.all([
+      program.account.petGenome.fetch(petGenome),
+      program.account.petStats.fetch(petStat1),
+      fetchMetadata(umi, metadata1),
+    ]);
+
+    const average = Math.floor((stats.hunger + stats.hygiene + stats.energy) / 3);
+    const mood =
+      average >= 70
+        ? "happy"
+        : average >= 40
+        ? "content"
+        : average >= 15
+        ? "sad"
+        : "miserable";
+    expect(onChainMetadata.name).to.equal(`Pet #${genome.number}`);
+    expect(onChainMetadata.uri).to.equal(
+      `https://solapet.example/pets/${genome.number}.json?level=${stats.level}&mood=${mood}`
+    );
+  });
+
+  it("Should unlock the NFT, revoke delegate, and keep the pet's stats", async () => {
     await program.methods
       .closePlayer()
       .accountsPartial({
@@ -739,13 +2178,12 @@ describe("solapet-capstone", () => {
       .signers([player1])
       .rpc();
 
-    // Verify that the pet stats account was closed
-    try {
-      await program.account.petStats.fetch(petStat1);
-      assert.fail("Pet stats account should have been closed");
-    } catch (error) {
-      expect(error.toString()).to.include("Account does not exist");
-    }
+    // Stats stay with the pet, but no wallet has it deposited any more.
+    const onChainStats = await program.account.petStats.fetch(petStat1);
+    expect(onChainStats.owner.toBase58()).to.equal(
+      PublicKey.default.toBase58()
+    );
+    expect(onChainStats.wins + onChainStats.losses).to.equal(1);
   });
 
   it("Should enable player to transfer the nfts back to their wallet", async () => {